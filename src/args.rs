@@ -1,9 +1,19 @@
+use std::net::IpAddr;
 use std::path::PathBuf;
 
+use ipnet::IpNet;
 use regex::Regex;
 use structopt::StructOpt;
 use url::Url;
 
+use super::canonical::{HostAlias, TrailingSlash};
+use super::depth::DepthRule;
+use super::filter::parse_glob;
+use super::frontier::{PriorityRule, Strategy};
+use super::resolver::HostOverride;
+use super::rewrite::RewriteRule;
+use super::scope::Scope;
+
 ///CLI arguments
 #[derive(Debug, StructOpt)]
 pub struct Args {
@@ -44,6 +54,191 @@ pub struct Args {
     )]
     pub ext_depth: i32,
 
+    /// Depth limits of the pages matching a regex
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Maximum depth of the internal pages matching a regex, as <regex>=<depth>, instead of --depth. With <regex>=nofollow the matching pages are saved but their links are not followed. The first matching rule applies. Can be repeated"
+    )]
+    pub depth_rule: Vec<DepthRule>,
+
+    /// Hosts considered part of the site
+    #[structopt(
+        long,
+        default_value = "host",
+        help = "Which links are internal, following --depth instead of --ext-depth: host (same host), domain (same registrable domain, like *.example.co.uk), subdomains-of:<domain> or list:<host>,<host>..."
+    )]
+    pub scope: Scope,
+
+    /// Confine the scraping to the directory of the origin
+    #[structopt(
+        long,
+        help = "Never ascend above the directory of the origin URL on its host, like wget's --no-parent"
+    )]
+    pub no_parent: bool,
+
+    /// Prefixes under which pages can be visited
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "URL, or path on the host of the origin, under which pages can be visited. The other pages of its host are skipped. Adds to --no-parent, can be repeated"
+    )]
+    pub scope_prefix: Vec<String>,
+
+    /// Let the images, stylesheets and scripts escape the prefixes
+    #[structopt(
+        long,
+        help = "Download the images, stylesheets and scripts of the pages even when they are outside of --no-parent and --scope-prefix"
+    )]
+    pub requisites_outside_prefix: bool,
+
+    /// Hosts serving the same site as another one
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Treat a host as another one, format: alias=host. Both share the directory of the latter, and are downloaded once. Can be repeated"
+    )]
+    pub host_alias: Vec<HostAlias>,
+
+    /// Treat the http and https URLs as the same
+    #[structopt(
+        long,
+        help = "Treat the http and https versions of a URL as the same page, downloaded once"
+    )]
+    pub unify_schemes: bool,
+
+    /// Treat the www and bare hosts as the same
+    #[structopt(
+        long,
+        help = "Treat www.example.com and example.com as the same host, saved in the directory of the latter"
+    )]
+    pub merge_www: bool,
+
+    /// Sort the query parameters
+    #[structopt(
+        long,
+        help = "Treat URLs whose query parameters only differ by their order as the same page"
+    )]
+    pub sort_query: bool,
+
+    /// Query parameters ignored when comparing URLs
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Remove a query parameter from the URLs, ending with * to remove all the parameters with this prefix, like utm_*. Can be repeated"
+    )]
+    pub drop_param: Vec<String>,
+
+    /// Drop the usual tracking parameters
+    #[structopt(
+        long,
+        help = "Remove the usual tracking parameters from the URLs, like utm_*, fbclid and gclid"
+    )]
+    pub drop_tracking_params: bool,
+
+    /// Drop the session IDs
+    #[structopt(
+        long,
+        help = "Remove the session IDs from the URLs, like ;jsessionid= and PHPSESSID=. Generic names like sid are kept, use --drop-param for them"
+    )]
+    pub strip_session_ids: bool,
+
+    /// Trailing slash policy
+    #[structopt(
+        long,
+        default_value = "keep",
+        help = "What to do with the slash at the end of the paths: keep it as is, add it to the paths without extension, or remove it"
+    )]
+    pub trailing_slash: TrailingSlash,
+
+    /// Compare the paths regardless of their case
+    #[structopt(
+        long,
+        help = "Treat paths differing only by their case as the same page, like on case-insensitive servers"
+    )]
+    pub case_insensitive_paths: bool,
+
+    /// Longest URL which is not a trap
+    #[structopt(
+        long,
+        help = "Skip the URLs longer than this, as crawler traps. 2048 is a good start"
+    )]
+    pub max_url_length: Option<usize>,
+
+    /// Most query parameters of an URL which is not a trap
+    #[structopt(
+        long,
+        help = "Skip the URLs with more query parameters than this, as crawler traps. 16 is a good start"
+    )]
+    pub max_query_params: Option<usize>,
+
+    /// Most repetitions of a path segment in an URL which is not a trap
+    #[structopt(
+        long,
+        help = "Skip the URLs repeating a path segment more than this, like /a/b/a/b/a/b, as crawler traps. 2 is a good start"
+    )]
+    pub max_segment_repeats: Option<usize>,
+
+    /// Most URLs sharing a pattern before they become traps
+    #[structopt(
+        long,
+        help = "Skip the URLs once this many URLs share their pattern, which is their path with the numbers replaced and the names of their query parameters, like calendars and faceted search. 1000 is a good start. The patterns are counted in memory"
+    )]
+    pub max_urls_per_pattern: Option<usize>,
+
+    /// Hamming distance under which pages are near-duplicates
+    #[structopt(
+        long,
+        help = "Save the pages whose text has a SimHash within this many bits of an already downloaded page as links to it, without following their links. 3 is a good start. The SimHashes of the saved pages are kept in memory"
+    )]
+    pub near_duplicate_distance: Option<u32>,
+
+    /// If set, detect the not-found pages served with a 200 status
+    #[structopt(
+        long,
+        help = "Probe each host with a random URL to learn its \"page not found\" template, and skip the pages served with it as broken links"
+    )]
+    pub detect_soft_404s: bool,
+
+    /// If set, only download the origin page and what is needed to display it
+    #[structopt(
+        long,
+        help = "Download only the origin page with its requisites from any host, and the frames it displays with theirs. Other links are made absolute"
+    )]
+    pub page: bool,
+
+    /// Media types of the files to download
+    #[structopt(
+        long,
+        use_delimiter = true,
+        help = "Only download the files with one of these media types, like image/* or application/pdf. Pages are still visited for their links. The type of the files sent without one is sniffed from their content"
+    )]
+    pub accept_mime: Vec<String>,
+
+    /// Media types of the files not to download
+    #[structopt(
+        long,
+        use_delimiter = true,
+        help = "Don't download the files with one of these media types, like video/*. Pages are still visited for their links"
+    )]
+    pub reject_mime: Vec<String>,
+
+    /// Minimum size of the files to download
+    #[structopt(
+        long,
+        parse(try_from_str = parse_size),
+        help = "Don't download the files smaller than this many bytes. Accepts the K, M and G suffixes"
+    )]
+    pub min_size: Option<u64>,
+
+    /// Maximum size of the files to download
+    #[structopt(
+        long,
+        parse(try_from_str = parse_size),
+        help = "Abort the downloads larger than this many bytes. Accepts the K, M and G suffixes"
+    )]
+    pub max_size: Option<u64>,
+
     ///Number of retries when downloading a page fails
     #[structopt(
         short,
@@ -61,6 +256,180 @@ pub struct Args {
     )]
     pub verbose: bool,
 
+    /// Static host resolutions, bypassing DNS
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Resolve a host to the given addresses instead of using DNS, format: host:*:addr[,addr]. The override applies to every port of the host, so the port must be *. Can be repeated"
+    )]
+    pub resolve: Vec<HostOverride>,
+
+    /// Refuse the addresses which are not publicly routable
+    #[structopt(
+        long,
+        help = "Refuse to download from loopback, link-local, private and multicast addresses, whether they are reached directly, through DNS or through a redirection. The proxies set in the environment are not used"
+    )]
+    pub deny_private_networks: bool,
+
+    /// Exceptions to --deny-private-networks
+    #[structopt(
+        long,
+        number_of_values = 1,
+        parse(try_from_str = parse_network),
+        help = "Network, in CIDR notation, or address which can be downloaded from despite --deny-private-networks. Can be repeated"
+    )]
+    pub allow_network: Vec<IpNet>,
+
+    /// Timeout of the connection to the server
+    #[structopt(
+        long,
+        help = "Maximum time in seconds to wait for the connection to a server to be established"
+    )]
+    pub connect_timeout: Option<u64>,
+
+    /// Timeout of each read from the server
+    #[structopt(
+        long,
+        default_value = "30",
+        help = "Maximum time in seconds to wait for data from a server. 0 means no timeout"
+    )]
+    pub read_timeout: u64,
+
+    /// Timeout of a whole download, retries included
+    #[structopt(
+        long,
+        help = "Maximum time in seconds to download a page, retries included"
+    )]
+    pub request_timeout: Option<u64>,
+
+    /// Deadline of the whole scraping
+    #[structopt(
+        long,
+        help = "Stop the scraping after this many seconds. The downloads in progress finish, unless --abort-in-flight is set"
+    )]
+    pub max_duration: Option<u64>,
+
+    /// Deadline of the scraping of each host
+    #[structopt(
+        long,
+        help = "Stop downloading from a host this many seconds after its first download"
+    )]
+    pub max_duration_per_host: Option<u64>,
+
+    /// Maximum number of downloads
+    #[structopt(
+        long,
+        help = "Stop the scraping after downloading this many pages and files"
+    )]
+    pub max_pages: Option<u64>,
+
+    /// Maximum number of downloads from each host
+    #[structopt(
+        long,
+        help = "Stop downloading from a host after this many pages and files"
+    )]
+    pub max_pages_per_host: Option<u64>,
+
+    /// Maximum number of bytes downloaded
+    #[structopt(
+        long,
+        parse(try_from_str = parse_size),
+        help = "Stop the scraping after downloading this many bytes. Accepts the K, M and G suffixes, like 5G"
+    )]
+    pub max_bytes: Option<u64>,
+
+    /// Maximum number of bytes downloaded from each host
+    #[structopt(
+        long,
+        parse(try_from_str = parse_size),
+        help = "Stop downloading from a host after this many bytes. Accepts the K, M and G suffixes, like 500M"
+    )]
+    pub max_bytes_per_host: Option<u64>,
+
+    /// If set, abort the downloads in progress when a budget is exhausted
+    #[structopt(
+        long,
+        help = "Abort the downloads in progress when a budget of --max-duration, --max-bytes or their per host versions is exhausted, even by a single download, instead of letting them finish"
+    )]
+    pub abort_in_flight: bool,
+
+    /// Directory of the database keeping the state of very large crawls
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Keep the queued and seen URLs in a database in this directory instead of the memory, for very large crawls. Without it, --max-urls-in-memory bounds the memory they use. The database is deleted once the crawl is over"
+    )]
+    pub state_dir: Option<PathBuf>,
+
+    /// Number of queued or seen URLs beyond which they are moved to a temporary database
+    #[structopt(
+        long,
+        help = "Move the queued URLs, and the seen URLs, to a database in the temporary directory once more than this many of them are kept in memory. Ignored with --state-dir"
+    )]
+    pub max_urls_in_memory: Option<usize>,
+
+    /// Size of the Bloom filter in front of the seen URLs of the database
+    #[structopt(
+        long,
+        help = "Check the seen URLs of the database of --state-dir or --max-urls-in-memory with a Bloom filter sized for this many URLs first, sparing database reads for the new ones"
+    )]
+    pub bloom_filter: Option<usize>,
+
+    /// Order of the downloads
+    #[structopt(
+        long,
+        default_value = "bfs",
+        help = "Order of the downloads of each host: bfs (closest to the origin first), dfs (last found first), requisites-first (images, stylesheets and scripts first, then bfs) or shortest-url. The hosts take turns, so the order is not followed across hosts"
+    )]
+    pub strategy: Strategy,
+
+    /// Priorities of the URLs matching a regex
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Priority of the URLs matching a regex, as <regex>=<priority>. URLs with a higher priority are downloaded first whatever the strategy, the default priority being 0. The first matching rule applies. Can be repeated"
+    )]
+    pub priority: Vec<PriorityRule>,
+
+    /// Maximum number of concurrent downloads from a host
+    #[structopt(
+        long,
+        help = "Maximum number of jobs downloading from the same host at once. The hosts whose next URLs have the highest priority take turns in any case, so none of them starves the others"
+    )]
+    pub max_jobs_per_host: Option<usize>,
+
+    /// Bandwidth limit of all the downloads
+    #[structopt(
+        long,
+        parse(try_from_str = parse_size),
+        help = "Maximum download speed in bytes per second, shared by all the jobs. Accepts the K, M and G suffixes, like 500K"
+    )]
+    pub limit_rate: Option<u64>,
+
+    /// Bandwidth limit of the downloads from each host
+    #[structopt(
+        long,
+        parse(try_from_str = parse_size),
+        help = "Maximum download speed in bytes per second from a single host. Accepts the K, M and G suffixes, like 500K"
+    )]
+    pub limit_rate_per_host: Option<u64>,
+
+    /// Directory of the HTTP cache
+    #[structopt(
+        long,
+        parse(from_os_str),
+        help = "Keep an HTTP cache in this directory, reused by the next runs. Fresh pages are not downloaded again, stale ones are revalidated with the server"
+    )]
+    pub cache_dir: Option<PathBuf>,
+
+    /// If set, only use the cache
+    #[structopt(
+        long,
+        requires = "cache-dir",
+        help = "Serve every page from the cache, even stale ones, without using the network. Pages which are not in the cache are skipped"
+    )]
+    pub offline: bool,
+
     /// The least seconds of delay between downloads
     #[structopt(
         long,
@@ -94,48 +463,99 @@ pub struct Args {
     )]
     pub cookie: String,
 
-    /// Regex filter to limit visiting pages to only matched ones
+    /// Regex filters to limit visiting pages to only matched ones
     #[structopt(
     long,
-    default_value = ".*",
+    number_of_values = 1,
     parse(try_from_str = parse_regex),
-    help = "Regex filter to limit to only visiting pages that match this expression"
+    help = "Regex filter to limit to only visiting pages that match this expression. Can be repeated to match any of them"
     )]
-    pub include_visit: Regex,
+    pub include_visit: Vec<Regex>,
 
-    /// Regex filter to limit visiting pages to only matched ones
+    /// Regex filters to limit visiting pages to only matched ones
     #[structopt(
     long,
-    default_value = "$^",
+    number_of_values = 1,
     parse(try_from_str = parse_regex),
-    help = "Regex filter to exclude visiting pages that match this expression"
+    help = "Regex filter to exclude visiting pages that match this expression. Can be repeated"
     )]
-    pub exclude_visit: Regex,
+    pub exclude_visit: Vec<Regex>,
 
-    /// Regex filter to limit saving pages to only matched ones
+    /// Regex filters to limit saving pages to only matched ones
     #[structopt(
     short,
     long,
-    default_value = ".*",
+    number_of_values = 1,
     parse(try_from_str = parse_regex),
-    help = "Regex filter to limit to only saving pages that match this expression"
+    help = "Regex filter to limit to only saving pages that match this expression. Can be repeated to match any of them"
     )]
-    pub include_download: Regex,
+    pub include_download: Vec<Regex>,
 
-    /// Regex filter to limit saving pages to only matched ones
+    /// Regex filters to limit saving pages to only matched ones
     #[structopt(
     short,
     long,
-    default_value = "$^",
+    number_of_values = 1,
     parse(try_from_str = parse_regex),
-    help = "Regex filter to exclude saving pages that match this expression"
+    help = "Regex filter to exclude saving pages that match this expression. Can be repeated"
+    )]
+    pub exclude_download: Vec<Regex>,
+
+    /// Glob filters to limit saving files to only matched ones
+    #[structopt(
+        long,
+        number_of_values = 1,
+        parse(try_from_str = parse_glob),
+        help = "Glob filter to limit to only saving files that match it, like '*.pdf'. Globs starting with / match the whole path, others its end. * stays within a directory, ** doesn't. Can be repeated"
+    )]
+    pub accept: Vec<String>,
+
+    /// Glob filters to exclude saving files matching them
+    #[structopt(
+        long,
+        number_of_values = 1,
+        parse(try_from_str = parse_glob),
+        help = "Glob filter to exclude saving files that match it, like '/private/**'. Can be repeated"
+    )]
+    pub reject: Vec<String>,
+
+    /// Rule files deciding which pages are visited
+    #[structopt(
+        long,
+        number_of_values = 1,
+        parse(from_os_str),
+        help = "File of rules deciding which pages are visited, one per line like 'allow <regex>' or 'deny glob:<glob>'. The first matching rule wins, before the other filters. Can be repeated"
+    )]
+    pub visit_rules: Vec<PathBuf>,
+
+    /// Rule files deciding which pages are saved
+    #[structopt(
+        long,
+        number_of_values = 1,
+        parse(from_os_str),
+        help = "File of rules deciding which pages are saved, in the format of --visit-rules. Can be repeated"
+    )]
+    pub download_rules: Vec<PathBuf>,
+
+    /// Rules rewriting the URLs of the links before they are fetched
+    #[structopt(
+        long,
+        number_of_values = 1,
+        help = "Rewrite the URLs of the links before fetching them, as '<regex> <replacement>' where the replacement can use the groups of the regex like $1. The first matching rule applies. Can be repeated"
+    )]
+    pub rewrite: Vec<RewriteRule>,
+
+    /// If set, name the files of rewritten URLs after their original URL
+    #[structopt(
+        long,
+        help = "Name the files of rewritten URLs after their original URL, so the saved pages keep the paths of the site"
     )]
-    pub exclude_download: Regex,
+    pub rewrite_keep_paths: bool,
 
     /// If set, set the visit filter to the values of the download filter
     #[structopt(
         long,
-        help = "Use the dowload filter in/exclude regexes for visiting as well"
+        help = "Use the dowload filters, globs and rules for visiting as well"
     )]
     pub visit_filter_is_download_filter: bool,
 
@@ -159,6 +579,13 @@ pub struct Args {
 
     #[structopt(long, help = "Dissable SSL certificates verification")]
     pub disable_certs_checks: bool,
+
+    /// If set, write copies of files instead of creating symlinks to them
+    #[structopt(
+        long,
+        help = "Copy files named by a Content-Disposition header and near-duplicate pages instead of symlinking them"
+    )]
+    pub no_symlinks: bool,
 }
 
 impl Args {
@@ -168,6 +595,48 @@ impl Args {
     }
 }
 
+fn parse_size(src: &str) -> Result<u64, String> {
+    let (number, multiplier) = match src.chars().last() {
+        Some('k' | 'K') => (&src[..src.len() - 1], 1024),
+        Some('m' | 'M') => (&src[..src.len() - 1], 1024 * 1024),
+        Some('g' | 'G') => (&src[..src.len() - 1], 1024 * 1024 * 1024),
+        _ => (src, 1),
+    };
+
+    match number.parse::<u64>() {
+        Ok(number) if number > 0 => number
+            .checked_mul(multiplier)
+            .ok_or_else(|| format!("Size {} is too large", src)),
+        _ => Err(format!(
+            "Invalid size {}, expected a positive number of bytes like 500K",
+            src
+        )),
+    }
+}
+
+fn parse_network(src: &str) -> Result<IpNet, String> {
+    src.parse::<IpNet>()
+        .or_else(|_| src.parse::<IpAddr>().map(IpNet::from))
+        .map_err(|_| format!("Invalid network {}, expected an address or a CIDR", src))
+}
+
 fn parse_regex(src: &str) -> Result<Regex, regex::Error> {
     Regex::new(src)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512"), Ok(512));
+        assert_eq!(parse_size("500K"), Ok(500 * 1024));
+        assert_eq!(parse_size("2m"), Ok(2 * 1024 * 1024));
+        assert_eq!(parse_size("1G"), Ok(1024 * 1024 * 1024));
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("K").is_err());
+        assert!(parse_size("1.5M").is_err());
+        assert!(parse_size("20000000000G").is_err());
+    }
+}
@@ -0,0 +1,70 @@
+/// Rate of false positives the filters are sized for
+const FALSE_POSITIVE_RATE: f64 = 0.01;
+
+/// A Bloom filter, telling with a fixed amount of memory if a key was surely never inserted
+/// or was probably inserted
+pub struct BloomFilter {
+    bits: Vec<u64>,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// Create a filter for `capacity` keys, having about 1% of false positives once full
+    pub fn new(capacity: usize) -> BloomFilter {
+        let capacity = capacity.max(1) as f64;
+        let ln2 = std::f64::consts::LN_2;
+        let bits = (-capacity * FALSE_POSITIVE_RATE.ln() / (ln2 * ln2)).ceil() as usize;
+        let hashes = ((bits as f64 / capacity) * ln2).round().max(1.0) as u32;
+
+        BloomFilter {
+            bits: vec![0; bits / 64 + 1],
+            hashes,
+        }
+    }
+
+    /// Return the bits of a key, derived from two hashes
+    fn positions(&self, key: &str) -> impl Iterator<Item = usize> {
+        let digest = md5::compute(key).0;
+        let mut halves = [0; 8];
+        halves.copy_from_slice(&digest[..8]);
+        let first = u64::from_le_bytes(halves);
+        halves.copy_from_slice(&digest[8..]);
+        let second = u64::from_le_bytes(halves);
+
+        let len = (self.bits.len() * 64) as u64;
+        (0..u64::from(self.hashes))
+            .map(move |i| (first.wrapping_add(i.wrapping_mul(second)) % len) as usize)
+    }
+
+    /// Add a key to the filter
+    pub fn insert(&mut self, key: &str) {
+        for position in self.positions(key).collect::<Vec<usize>>() {
+            self.bits[position / 64] |= 1 << (position % 64);
+        }
+    }
+
+    /// Check if a key may have been inserted. `false` means it surely was not
+    pub fn may_contain(&self, key: &str) -> bool {
+        self.positions(key)
+            .all(|position| self.bits[position / 64] & (1 << (position % 64)) != 0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn false_positives() {
+        let mut filter = BloomFilter::new(1000);
+        for i in 0..1000 {
+            filter.insert(&format!("https://example.com/{}", i));
+        }
+
+        assert!((0..1000).all(|i| filter.may_contain(&format!("https://example.com/{}", i))));
+        let false_positives = (0..1000)
+            .filter(|i| filter.may_contain(&format!("https://example.org/{}", i)))
+            .count();
+        assert!(false_positives < 50, "{} false positives", false_positives);
+    }
+}
@@ -0,0 +1,310 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use url::Url;
+
+use super::args;
+
+/// Which limit of a budget was reached
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BudgetReason {
+    /// The maximum number of downloads
+    Pages(u64),
+    /// The maximum number of bytes downloaded
+    Bytes(u64),
+    /// The maximum duration, in seconds
+    Duration(u64),
+}
+
+impl fmt::Display for BudgetReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetReason::Pages(max) => write!(f, "Maximum number of pages reached ({})", max),
+            BudgetReason::Bytes(max) => write!(f, "Maximum number of bytes reached ({})", max),
+            BudgetReason::Duration(max) => write!(f, "Maximum duration reached ({}s)", max),
+        }
+    }
+}
+
+/// A budget which is exhausted, the one of the whole crawl or of a host
+#[derive(Debug, Clone, PartialEq)]
+pub struct Exhausted {
+    pub host: Option<String>,
+    pub reason: BudgetReason,
+}
+
+impl fmt::Display for Exhausted {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.host {
+            Some(host) => write!(f, "{} for {}", self.reason, host),
+            None => write!(f, "{}", self.reason),
+        }
+    }
+}
+
+/// The limits of a budget
+#[derive(Debug, Clone, Copy)]
+struct Limits {
+    pages: Option<u64>,
+    bytes: Option<u64>,
+    duration: Option<u64>,
+}
+
+/// What was consumed of a budget
+#[derive(Debug, Clone, Copy)]
+struct Usage {
+    pages: u64,
+    bytes: u64,
+    start: Instant,
+}
+
+impl Usage {
+    fn new() -> Usage {
+        Usage {
+            pages: 0,
+            bytes: 0,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Limits {
+    /// Check if a budget is exhausted. Starting a download needs a page left, while the
+    /// downloads in progress already have theirs
+    fn check(&self, usage: &Usage, starting: bool) -> Result<(), BudgetReason> {
+        if let Some(max) = self.duration {
+            if usage.start.elapsed() >= Duration::from_secs(max) {
+                return Err(BudgetReason::Duration(max));
+            }
+        }
+        if let Some(max) = self.bytes {
+            if usage.bytes >= max {
+                return Err(BudgetReason::Bytes(max));
+            }
+        }
+        if let Some(max) = self.pages {
+            if usage.pages > max || (starting && usage.pages == max) {
+                return Err(BudgetReason::Pages(max));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Check if a download in progress, which already read `pending` bytes, exceeds a budget
+    fn check_transfer(&self, usage: &Usage, pending: u64) -> Result<(), BudgetReason> {
+        self.check(usage, false)?;
+
+        match self.bytes {
+            Some(max) if usage.bytes + pending > max => Err(BudgetReason::Bytes(max)),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Limits the number of pages, the bytes and the duration of the crawl, as a whole and for
+/// each host. A host's duration starts with its first download
+pub struct Budget {
+    crawl: Limits,
+    host: Limits,
+    crawl_usage: Mutex<Usage>,
+    host_usage: Mutex<HashMap<String, Usage>>,
+}
+
+impl Budget {
+    /// Create the budgets given on the command line, starting the duration of the crawl
+    pub fn new(args: &args::Args) -> Budget {
+        Budget {
+            crawl: Limits {
+                pages: args.max_pages,
+                bytes: args.max_bytes,
+                duration: args.max_duration,
+            },
+            host: Limits {
+                pages: args.max_pages_per_host,
+                bytes: args.max_bytes_per_host,
+                duration: args.max_duration_per_host,
+            },
+            crawl_usage: Mutex::new(Usage::new()),
+            host_usage: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the key of the budget of the host of an URL
+    fn host_key(url: &Url) -> String {
+        url.host_str().unwrap_or_default().to_string()
+    }
+
+    /// Check if the budget of the whole crawl is exhausted, so no download can be started
+    pub fn check_crawl(&self) -> Result<(), Exhausted> {
+        self.crawl
+            .check(&self.crawl_usage.lock().unwrap(), true)
+            .map_err(|reason| Exhausted { host: None, reason })
+    }
+
+    /// Check if `url` can still be downloaded, or for a download in progress if it was started
+    /// before the budgets ran out
+    pub fn check(&self, url: &Url, starting: bool) -> Result<(), Exhausted> {
+        self.crawl
+            .check(&self.crawl_usage.lock().unwrap(), starting)
+            .map_err(|reason| Exhausted { host: None, reason })?;
+
+        let host = Budget::host_key(url);
+        match self.host_usage.lock().unwrap().get(&host) {
+            Some(usage) => self
+                .host
+                .check(usage, starting)
+                .map_err(|reason| Exhausted {
+                    host: Some(host),
+                    reason,
+                }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check if the download of `url` in progress can go on, with the `pending` bytes it read
+    /// so far which are not accounted for yet
+    pub fn check_transfer(&self, url: &Url, pending: u64) -> Result<(), Exhausted> {
+        self.crawl
+            .check_transfer(&self.crawl_usage.lock().unwrap(), pending)
+            .map_err(|reason| Exhausted { host: None, reason })?;
+
+        let host = Budget::host_key(url);
+        match self.host_usage.lock().unwrap().get(&host) {
+            Some(usage) => self
+                .host
+                .check_transfer(usage, pending)
+                .map_err(|reason| Exhausted {
+                    host: Some(host),
+                    reason,
+                }),
+            None => Ok(()),
+        }
+    }
+
+    /// Take a page of the budgets to download `url`, if they are not exhausted
+    pub fn start(&self, url: &Url) -> Result<(), Exhausted> {
+        let mut crawl_usage = self.crawl_usage.lock().unwrap();
+        let mut host_usage = self.host_usage.lock().unwrap();
+
+        self.crawl
+            .check(&crawl_usage, true)
+            .map_err(|reason| Exhausted { host: None, reason })?;
+        let host = Budget::host_key(url);
+        let usage = host_usage.entry(host.clone()).or_insert_with(Usage::new);
+        self.host.check(usage, true).map_err(|reason| Exhausted {
+            host: Some(host),
+            reason,
+        })?;
+
+        crawl_usage.pages += 1;
+        usage.pages += 1;
+        Ok(())
+    }
+
+    /// Account for the bytes downloaded from `url`
+    pub fn add_bytes(&self, url: &Url, bytes: u64) {
+        self.crawl_usage.lock().unwrap().bytes += bytes;
+        if let Some(usage) = self
+            .host_usage
+            .lock()
+            .unwrap()
+            .get_mut(&Budget::host_key(url))
+        {
+            usage.bytes += bytes;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use structopt::StructOpt;
+
+    use super::*;
+
+    fn budget(flags: &[&str]) -> Budget {
+        let mut args = vec!["suckit", "https://example.com"];
+        args.extend_from_slice(flags);
+
+        Budget::new(&args::Args::from_iter(args))
+    }
+
+    #[test]
+    fn pages() {
+        let budget = budget(&["--max-pages", "3", "--max-pages-per-host", "2"]);
+        let a = Url::parse("https://a.com/").unwrap();
+        let b = Url::parse("https://b.com/").unwrap();
+
+        assert!(budget.start(&a).is_ok());
+        assert!(budget.start(&a).is_ok());
+        assert_eq!(
+            budget.start(&a),
+            Err(Exhausted {
+                host: Some("a.com".to_string()),
+                reason: BudgetReason::Pages(2)
+            })
+        );
+        // The downloads in progress are within the budget
+        assert!(budget.check(&a, false).is_ok());
+
+        assert!(budget.start(&b).is_ok());
+        assert_eq!(
+            budget.check_crawl(),
+            Err(Exhausted {
+                host: None,
+                reason: BudgetReason::Pages(3)
+            })
+        );
+        assert!(budget.start(&b).is_err());
+    }
+
+    #[test]
+    fn bytes() {
+        let budget = budget(&["--max-bytes-per-host", "1K"]);
+        let a = Url::parse("https://a.com/").unwrap();
+        let b = Url::parse("https://b.com/").unwrap();
+
+        assert!(budget.start(&a).is_ok());
+        assert!(budget.start(&b).is_ok());
+        budget.add_bytes(&a, 2048);
+
+        assert_eq!(
+            budget.check(&a, false).unwrap_err().to_string(),
+            "Maximum number of bytes reached (1024) for a.com"
+        );
+        assert!(budget.start(&a).is_err());
+        assert!(budget.start(&b).is_ok());
+        assert!(budget.check_crawl().is_ok());
+    }
+
+    #[test]
+    fn transfer() {
+        let budget = budget(&["--max-bytes", "1K"]);
+        let a = Url::parse("https://a.com/").unwrap();
+
+        assert!(budget.start(&a).is_ok());
+        assert!(budget.check_transfer(&a, 1024).is_ok());
+        // A single download larger than the budget is stopped while it is read
+        assert_eq!(
+            budget.check_transfer(&a, 1025),
+            Err(Exhausted {
+                host: None,
+                reason: BudgetReason::Bytes(1024)
+            })
+        );
+        budget.add_bytes(&a, 512);
+        assert!(budget.check_transfer(&a, 513).is_err());
+    }
+
+    #[test]
+    fn duration() {
+        let budget = budget(&["--max-duration-per-host", "0"]);
+        let a = Url::parse("https://a.com/").unwrap();
+
+        // The duration of a host starts with its first download
+        assert!(budget.check(&a, true).is_ok());
+        assert!(budget.start(&a).is_err());
+    }
+}
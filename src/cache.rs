@@ -0,0 +1,402 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use chrono::DateTime;
+use reqwest::header::{
+    HeaderMap, HeaderName, HeaderValue, AGE, CACHE_CONTROL, CONTENT_LENGTH, DATE, ETAG, EXPIRES,
+    IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED, VARY,
+};
+use url::Url;
+
+/// First line of the cache entries, to recognize them and their format
+const ENTRY_MAGIC: &[u8] = b"SUCKIT-CACHE 1";
+
+/// Status codes which can be cached without explicit freshness, as listed by RFC 9110
+const HEURISTICALLY_CACHEABLE: [u16; 11] = [200, 203, 204, 300, 301, 308, 404, 405, 410, 414, 501];
+
+/// Share of the time since the last modification used as heuristic freshness lifetime
+const HEURISTIC_FRACTION: i64 = 10;
+
+/// Current time, in seconds since the Unix epoch
+pub fn now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_secs() as i64)
+}
+
+/// Parse an HTTP date header into seconds since the Unix epoch
+fn parse_date(headers: &HeaderMap, name: HeaderName) -> Option<i64> {
+    let value = headers.get(name)?.to_str().ok()?;
+
+    DateTime::parse_from_rfc2822(value.trim())
+        .ok()
+        .map(|date| date.timestamp())
+}
+
+/// Remove the ASCII whitespace around some bytes, which may not be UTF-8
+fn trim_whitespace(bytes: &[u8]) -> &[u8] {
+    let start = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let end = bytes
+        .iter()
+        .rposition(|byte| !byte.is_ascii_whitespace())
+        .map_or(start, |end| end + 1);
+
+    &bytes[start..end]
+}
+
+/// Parse the `Cache-Control` directives, with lowercase names and unquoted values
+fn cache_control(headers: &HeaderMap) -> HashMap<String, Option<String>> {
+    headers
+        .get_all(CACHE_CONTROL)
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|value| value.split(','))
+        .filter_map(|directive| {
+            let (name, value) = match directive.split_once('=') {
+                Some((name, value)) => (name, Some(value.trim().trim_matches('"').to_string())),
+                None => (directive, None),
+            };
+            let name = name.trim().to_lowercase();
+
+            (!name.is_empty()).then_some((name, value))
+        })
+        .collect()
+}
+
+/// A response stored in the cache, with the time it was received
+#[derive(Debug, Clone, PartialEq)]
+pub struct CachedResponse {
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    pub stored_at: i64,
+}
+
+impl CachedResponse {
+    /// Check if a response may be stored, following RFC 9111 for a private cache
+    pub fn is_storable(status: u16, headers: &HeaderMap) -> bool {
+        let directives = cache_control(headers);
+        let varies_on_everything = headers
+            .get_all(VARY)
+            .iter()
+            .any(|value| trim_whitespace(value.as_bytes()) == b"*");
+
+        !directives.contains_key("no-store")
+            && !varies_on_everything
+            && (HEURISTICALLY_CACHEABLE.contains(&status)
+                || directives.contains_key("max-age")
+                || directives.contains_key("public")
+                || headers.contains_key(EXPIRES))
+    }
+
+    /// How long the response is fresh, in seconds
+    fn freshness_lifetime(&self) -> i64 {
+        let directives = cache_control(&self.headers);
+        let date = parse_date(&self.headers, DATE).unwrap_or(self.stored_at);
+
+        if let Some(Some(max_age)) = directives.get("max-age") {
+            return max_age.parse().unwrap_or(0);
+        }
+
+        if self.headers.contains_key(EXPIRES) {
+            // Invalid dates, like "0", mean that the response is already expired
+            return parse_date(&self.headers, EXPIRES).map_or(0, |expires| expires - date);
+        }
+
+        match parse_date(&self.headers, LAST_MODIFIED) {
+            Some(last_modified) if HEURISTICALLY_CACHEABLE.contains(&self.status) => {
+                (date - last_modified).max(0) / HEURISTIC_FRACTION
+            }
+            _ => 0,
+        }
+    }
+
+    /// How old the response is, in seconds
+    fn current_age(&self, now: i64) -> i64 {
+        let age = self
+            .headers
+            .get(AGE)
+            .and_then(|age| age.to_str().ok())
+            .and_then(|age| age.trim().parse::<i64>().ok())
+            .unwrap_or(0);
+        let apparent_age =
+            parse_date(&self.headers, DATE).map_or(0, |date| (self.stored_at - date).max(0));
+
+        apparent_age.max(age) + (now - self.stored_at).max(0)
+    }
+
+    /// Check if the response can be used without asking the server
+    pub fn is_fresh(&self, now: i64) -> bool {
+        !cache_control(&self.headers).contains_key("no-cache")
+            && self.freshness_lifetime() > self.current_age(now)
+    }
+
+    /// Headers making the request conditional, so the server can answer that nothing changed
+    pub fn validators(&self) -> HeaderMap {
+        let mut validators = HeaderMap::new();
+        if let Some(etag) = self.headers.get(ETAG) {
+            validators.insert(IF_NONE_MATCH, etag.clone());
+        }
+        if let Some(last_modified) = self.headers.get(LAST_MODIFIED) {
+            validators.insert(IF_MODIFIED_SINCE, last_modified.clone());
+        }
+
+        validators
+    }
+
+    /// Update the response with the headers of a `304 Not Modified` received at `now`
+    pub fn refresh(&mut self, headers: &HeaderMap, now: i64) {
+        for name in headers.keys() {
+            if name == CONTENT_LENGTH {
+                continue;
+            }
+
+            self.headers.remove(name);
+            for value in headers.get_all(name) {
+                self.headers.append(name.clone(), value.clone());
+            }
+        }
+        self.stored_at = now;
+    }
+
+    /// Serialize the entry: magic, time, status and headers on their own lines, then the body
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.body.len() + 1024);
+        bytes.extend_from_slice(ENTRY_MAGIC);
+        bytes.extend_from_slice(format!("\n{}\n{}\n", self.stored_at, self.status).as_bytes());
+        for (name, value) in &self.headers {
+            bytes.extend_from_slice(name.as_str().as_bytes());
+            bytes.extend_from_slice(b": ");
+            bytes.extend_from_slice(value.as_bytes());
+            bytes.push(b'\n');
+        }
+        bytes.push(b'\n');
+        bytes.extend_from_slice(&self.body);
+
+        bytes
+    }
+
+    /// Parse an entry written by `to_bytes`
+    fn from_bytes(bytes: &[u8]) -> Option<CachedResponse> {
+        let mut rest = bytes;
+        let mut next_line = || {
+            let end = rest.iter().position(|&byte| byte == b'\n')?;
+            let line = &rest[..end];
+            rest = &rest[end + 1..];
+            Some(line)
+        };
+
+        if next_line()? != ENTRY_MAGIC {
+            return None;
+        }
+        let stored_at = std::str::from_utf8(next_line()?).ok()?.parse().ok()?;
+        let status = std::str::from_utf8(next_line()?).ok()?.parse().ok()?;
+
+        let mut headers = HeaderMap::new();
+        loop {
+            let line = next_line()?;
+            if line.is_empty() {
+                break;
+            }
+
+            let separator = line.iter().position(|&byte| byte == b':')?;
+            let name = HeaderName::from_bytes(&line[..separator]).ok()?;
+            let value = HeaderValue::from_bytes(trim_whitespace(&line[separator + 1..])).ok()?;
+            headers.append(name, value);
+        }
+
+        Some(CachedResponse {
+            status,
+            headers,
+            body: rest.to_vec(),
+            stored_at,
+        })
+    }
+}
+
+/// An HTTP cache stored in a directory, with a file per URL
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    /// Create a cache in `dir`, which is created if needed
+    pub fn new(dir: &Path) -> io::Result<Cache> {
+        fs::create_dir_all(dir)?;
+
+        Ok(Cache {
+            dir: dir.to_path_buf(),
+        })
+    }
+
+    /// Path of the entry of an URL
+    fn entry_path(&self, url: &Url) -> PathBuf {
+        self.dir
+            .join(format!("{:x}", md5::compute(url.as_str().as_bytes())))
+    }
+
+    /// Load the response stored for an URL, if any
+    pub fn load(&self, url: &Url) -> Option<CachedResponse> {
+        let bytes = fs::read(self.entry_path(url)).ok()?;
+
+        CachedResponse::from_bytes(&bytes)
+    }
+
+    /// Store the response of an URL, replacing the previous one.
+    /// The entry is written aside then renamed, so readers never see it partially written
+    pub fn store(&self, url: &Url, response: &CachedResponse) -> io::Result<()> {
+        let path = self.entry_path(url);
+        let temporary_path = path.with_extension(format!("{:08x}.tmp", rand::random::<u32>()));
+
+        let mut file = fs::File::create(&temporary_path)?;
+        file.write_all(&response.to_bytes())?;
+        drop(file);
+
+        fs::rename(&temporary_path, &path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NOW: i64 = 1_700_000_000;
+
+    fn response(headers: &[(&'static str, &str)]) -> CachedResponse {
+        let mut header_map = HeaderMap::new();
+        for (name, value) in headers {
+            header_map.append(*name, HeaderValue::from_str(value).unwrap());
+        }
+
+        CachedResponse {
+            status: 200,
+            headers: header_map,
+            body: b"<html></html>".to_vec(),
+            stored_at: NOW,
+        }
+    }
+
+    #[test]
+    fn max_age_freshness() {
+        let cached = response(&[("cache-control", "public, max-age=60")]);
+
+        assert!(cached.is_fresh(NOW + 59));
+        assert!(!cached.is_fresh(NOW + 60));
+    }
+
+    #[test]
+    fn age_header_counts() {
+        let cached = response(&[("cache-control", "max-age=60"), ("age", "50")]);
+
+        assert!(cached.is_fresh(NOW + 9));
+        assert!(!cached.is_fresh(NOW + 10));
+    }
+
+    #[test]
+    fn expires_freshness() {
+        let cached = response(&[
+            ("date", "Tue, 14 Nov 2023 22:13:20 GMT"),
+            ("expires", "Tue, 14 Nov 2023 23:13:20 GMT"),
+        ]);
+        assert!(cached.is_fresh(NOW + 3599));
+        assert!(!cached.is_fresh(NOW + 3600));
+
+        let cached = response(&[("expires", "0")]);
+        assert!(!cached.is_fresh(NOW));
+    }
+
+    #[test]
+    fn heuristic_freshness() {
+        let cached = response(&[
+            ("date", "Tue, 14 Nov 2023 22:13:20 GMT"),
+            ("last-modified", "Sat, 04 Nov 2023 22:13:20 GMT"),
+        ]);
+
+        // A tenth of the ten days since the last modification
+        assert!(cached.is_fresh(NOW + 86399));
+        assert!(!cached.is_fresh(NOW + 86400));
+    }
+
+    #[test]
+    fn no_cache_needs_revalidation() {
+        let cached = response(&[("cache-control", "no-cache, max-age=3600")]);
+
+        assert!(!cached.is_fresh(NOW));
+    }
+
+    #[test]
+    fn trimmed_whitespace() {
+        assert_eq!(trim_whitespace(b" \t*\r"), b"*");
+        assert_eq!(trim_whitespace(b"a b"), b"a b");
+        assert_eq!(trim_whitespace(b"  "), b"");
+        assert_eq!(trim_whitespace(b""), b"");
+    }
+
+    #[test]
+    fn storable() {
+        let headers = response(&[("cache-control", "no-store")]).headers;
+        assert!(!CachedResponse::is_storable(200, &headers));
+
+        let headers = response(&[("vary", "*")]).headers;
+        assert!(!CachedResponse::is_storable(200, &headers));
+
+        let headers = response(&[]).headers;
+        assert!(CachedResponse::is_storable(200, &headers));
+        assert!(!CachedResponse::is_storable(500, &headers));
+
+        let headers = response(&[("cache-control", "max-age=60")]).headers;
+        assert!(CachedResponse::is_storable(500, &headers));
+    }
+
+    #[test]
+    fn validators_and_refresh() {
+        let mut cached = response(&[
+            ("etag", "\"v1\""),
+            ("last-modified", "Sat, 04 Nov 2023 22:13:20 GMT"),
+            ("cache-control", "max-age=0"),
+        ]);
+
+        let validators = cached.validators();
+        assert_eq!(validators.get(IF_NONE_MATCH).unwrap(), "\"v1\"");
+        assert_eq!(
+            validators.get(IF_MODIFIED_SINCE).unwrap(),
+            "Sat, 04 Nov 2023 22:13:20 GMT"
+        );
+
+        let mut not_modified = HeaderMap::new();
+        not_modified.insert(CACHE_CONTROL, HeaderValue::from_static("max-age=60"));
+        not_modified.insert(CONTENT_LENGTH, HeaderValue::from_static("0"));
+        cached.refresh(&not_modified, NOW + 100);
+
+        assert!(cached.is_fresh(NOW + 150));
+        assert_eq!(cached.headers.get(ETAG).unwrap(), "\"v1\"");
+        assert!(!cached.headers.contains_key(CONTENT_LENGTH));
+        assert_eq!(cached.body, b"<html></html>");
+    }
+
+    #[test]
+    fn store_and_load() {
+        let tempdir = mktemp::Temp::new_dir().unwrap();
+        let cache = Cache::new(&tempdir.as_path().join("cache")).unwrap();
+        let url = Url::parse("https://example.com/page?x=1").unwrap();
+        let cached = response(&[
+            ("content-type", "text/html; charset=utf-8"),
+            ("set-cookie", "a=1"),
+            ("set-cookie", "b=2"),
+        ]);
+
+        assert_eq!(cache.load(&url), None);
+        cache.store(&url, &cached).unwrap();
+        assert_eq!(cache.load(&url), Some(cached));
+        assert_eq!(
+            cache.load(&Url::parse("https://example.com/page").unwrap()),
+            None
+        );
+    }
+}
@@ -0,0 +1,413 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use lazy_static::lazy_static;
+use percent_encoding::percent_decode_str;
+use regex::Regex;
+use url::Url;
+
+use super::args;
+
+/// Query parameters used to track visitors, dropped by `--drop-tracking-params`
+const TRACKING_PARAMS: [&str; 10] = [
+    "utm_*", "fbclid", "gclid", "dclid", "gbraid", "wbraid", "msclkid", "yclid", "mc_cid", "mc_eid",
+];
+
+/// Query parameters holding session IDs, dropped by `--strip-session-ids`. Generic names like
+/// `sid` also hold other things, so they are left to `--drop-param`
+const SESSION_PARAMS: [&str; 5] = [
+    "jsessionid",
+    "phpsessid",
+    "aspsessionid*",
+    "sessionid",
+    "session_id",
+];
+
+/// A host serving the same site as another one, written `alias=host`
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostAlias {
+    pub alias: String,
+    pub host: String,
+}
+
+impl FromStr for HostAlias {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<HostAlias, String> {
+        match src.split_once('=') {
+            Some((alias, host)) if !alias.trim().is_empty() && !host.trim().is_empty() => {
+                Ok(HostAlias {
+                    alias: alias.trim().to_lowercase(),
+                    host: host.trim().to_lowercase(),
+                })
+            }
+            _ => Err(format!("Invalid host alias {}, expected alias=host", src)),
+        }
+    }
+}
+
+/// What to do with the slash at the end of the paths
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TrailingSlash {
+    /// Paths are left as they are
+    Keep,
+    /// Paths whose last segment has no extension get a trailing slash
+    Add,
+    /// The trailing slash is removed, except for the root
+    Remove,
+}
+
+impl FromStr for TrailingSlash {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<TrailingSlash, String> {
+        match src {
+            "keep" => Ok(TrailingSlash::Keep),
+            "add" => Ok(TrailingSlash::Add),
+            "remove" => Ok(TrailingSlash::Remove),
+            _ => Err(format!(
+                "Invalid trailing slash policy {}, expected keep, add or remove",
+                src
+            )),
+        }
+    }
+}
+
+/// Check if a query parameter name matches a pattern, which can end with a `*` wildcard
+fn matches_param(name: &str, pattern: &str) -> bool {
+    match pattern.strip_suffix('*') {
+        Some(prefix) => name.starts_with(prefix),
+        None => name == pattern,
+    }
+}
+
+/// Gives a single form to equivalent URLs, so they share a path on the disk and are
+/// downloaded once. Default ports are already removed by the URL parser
+#[derive(Debug, Clone, PartialEq)]
+pub struct Canonicalizer {
+    aliases: HashMap<String, String>,
+    unify_schemes: bool,
+    merge_www: bool,
+    sort_query: bool,
+    dropped_params: Vec<String>,
+    strip_session_ids: bool,
+    trailing_slash: TrailingSlash,
+    case_insensitive_paths: bool,
+}
+
+impl Canonicalizer {
+    /// Create a canonicalizer applying the rules enabled on the command line
+    pub fn new(args: &args::Args) -> Canonicalizer {
+        let mut dropped_params: Vec<String> = args
+            .drop_param
+            .iter()
+            .map(|param| param.to_lowercase())
+            .collect();
+        if args.drop_tracking_params {
+            dropped_params.extend(TRACKING_PARAMS.iter().map(|param| param.to_string()));
+        }
+        if args.strip_session_ids {
+            dropped_params.extend(SESSION_PARAMS.iter().map(|param| param.to_string()));
+        }
+
+        Canonicalizer {
+            aliases: args
+                .host_alias
+                .iter()
+                .map(|alias| (alias.alias.clone(), alias.host.clone()))
+                .collect(),
+            unify_schemes: args.unify_schemes,
+            merge_www: args.merge_www,
+            sort_query: args.sort_query,
+            dropped_params,
+            strip_session_ids: args.strip_session_ids,
+            trailing_slash: args.trailing_slash,
+            case_insensitive_paths: args.case_insensitive_paths,
+        }
+    }
+
+    /// Return the canonical form of the path of an URL
+    fn canonicalize_path(&self, path: &str) -> String {
+        lazy_static! {
+            static ref SESSION_ID_REGEX: Regex = Regex::new(r"(?i);jsessionid=[^/;]*").unwrap();
+        }
+
+        let mut path = if self.strip_session_ids {
+            SESSION_ID_REGEX.replace_all(path, "").into_owned()
+        } else {
+            path.to_string()
+        };
+
+        if self.case_insensitive_paths {
+            path = path.to_lowercase();
+        }
+
+        match self.trailing_slash {
+            TrailingSlash::Add => {
+                let last_segment = path.rsplit('/').next().unwrap_or("");
+                if !last_segment.is_empty() && !last_segment.contains('.') {
+                    path.push('/');
+                }
+            }
+            TrailingSlash::Remove if path.len() > 1 && path.ends_with('/') => {
+                path.pop();
+            }
+            _ => (),
+        }
+
+        path
+    }
+
+    /// Return the canonical form of the query of an URL, `None` if it ends up empty
+    fn canonicalize_query(&self, query: &str) -> Option<String> {
+        let mut params: Vec<&str> = query
+            .split('&')
+            .filter(|param| !param.is_empty())
+            .filter(|param| {
+                let name = param.split('=').next().unwrap_or("");
+                let name = percent_decode_str(name).decode_utf8_lossy().to_lowercase();
+                !self
+                    .dropped_params
+                    .iter()
+                    .any(|pattern| matches_param(&name, pattern))
+            })
+            .collect();
+
+        if self.sort_query {
+            params.sort_unstable();
+        }
+
+        (!params.is_empty()).then(|| params.join("&"))
+    }
+
+    /// Return the canonical form of an URL
+    pub fn canonicalize(&self, url: &Url) -> Url {
+        let mut canonical = url.clone();
+
+        if !canonical.cannot_be_a_base() {
+            let path = self.canonicalize_path(canonical.path());
+            canonical.set_path(&path);
+        }
+
+        if let Some(query) = url.query() {
+            let query = self.canonicalize_query(query);
+            canonical.set_query(query.as_deref());
+        }
+
+        if self.unify_schemes && canonical.scheme() == "http" {
+            // The port is dropped if it was the default one of http
+            canonical.set_scheme("https").unwrap();
+        }
+
+        if let Some(host) = url.host_str() {
+            let mut canonical_host = host;
+            if self.merge_www {
+                canonical_host = canonical_host
+                    .strip_prefix("www.")
+                    .unwrap_or(canonical_host);
+            }
+            if let Some(aliased) = self.aliases.get(canonical_host) {
+                canonical_host = aliased;
+            }
+
+            if canonical_host != host && canonical.set_host(Some(canonical_host)).is_err() {
+                return url.clone();
+            }
+        }
+
+        canonical
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use structopt::StructOpt;
+
+    use super::*;
+
+    fn canonicalizer(flags: &[&str]) -> Canonicalizer {
+        let mut args = vec!["suckit", "https://example.com"];
+        args.extend_from_slice(flags);
+
+        Canonicalizer::new(&args::Args::from_iter(args))
+    }
+
+    fn canonicalize(canonicalizer: &Canonicalizer, url: &str) -> String {
+        canonicalizer
+            .canonicalize(&Url::parse(url).unwrap())
+            .to_string()
+    }
+
+    #[test]
+    fn parse_host_alias() {
+        assert_eq!(
+            "Example.org=example.com".parse(),
+            Ok(HostAlias {
+                alias: "example.org".to_string(),
+                host: "example.com".to_string(),
+            })
+        );
+        assert!("example.org".parse::<HostAlias>().is_err());
+        assert!("=example.com".parse::<HostAlias>().is_err());
+    }
+
+    #[test]
+    fn disabled() {
+        let canonicalizer = canonicalizer(&[]);
+
+        assert_eq!(
+            canonicalize(&canonicalizer, "http://www.example.com/a"),
+            "http://www.example.com/a"
+        );
+    }
+
+    #[test]
+    fn unify_schemes() {
+        let canonicalizer = canonicalizer(&["--unify-schemes"]);
+
+        assert_eq!(
+            canonicalize(&canonicalizer, "http://example.com/a?b#c"),
+            "https://example.com/a?b#c"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "http://example.com:80/"),
+            "https://example.com/"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "http://example.com:8080/"),
+            "https://example.com:8080/"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "ftp://example.com/"),
+            "ftp://example.com/"
+        );
+    }
+
+    #[test]
+    fn merge_www() {
+        let canonicalizer = canonicalizer(&["--merge-www"]);
+
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://www.example.com/"),
+            "https://example.com/"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://www2.example.com/"),
+            "https://www2.example.com/"
+        );
+    }
+
+    #[test]
+    fn host_aliases() {
+        let canonicalizer = canonicalizer(&[
+            "--host-alias",
+            "example.org=example.com",
+            "--unify-schemes",
+            "--merge-www",
+        ]);
+
+        assert_eq!(
+            canonicalize(&canonicalizer, "http://www.example.org/page"),
+            "https://example.com/page"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://example.net/"),
+            "https://example.net/"
+        );
+    }
+
+    #[test]
+    fn sort_query() {
+        let canonicalizer = canonicalizer(&["--sort-query"]);
+
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://example.com/a?y=2&x=1&&"),
+            "https://example.com/a?x=1&y=2"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://example.com/a?"),
+            "https://example.com/a"
+        );
+    }
+
+    #[test]
+    fn drop_params() {
+        let canonicalizer = canonicalizer(&["--drop-tracking-params", "--drop-param", "ref"]);
+
+        assert_eq!(
+            canonicalize(
+                &canonicalizer,
+                "https://example.com/a?id=3&utm_source=feed&UTM_Medium=rss&ref=home&fbclid=x"
+            ),
+            "https://example.com/a?id=3"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://example.com/a?utm_campaign=z"),
+            "https://example.com/a"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://example.com/a?reference=1"),
+            "https://example.com/a?reference=1"
+        );
+    }
+
+    #[test]
+    fn strip_session_ids() {
+        let canonicalizer = canonicalizer(&["--strip-session-ids"]);
+
+        assert_eq!(
+            canonicalize(
+                &canonicalizer,
+                "https://example.com/shop;jsessionid=A1B2C3/cart.jsp?PHPSESSID=42&item=7"
+            ),
+            "https://example.com/shop/cart.jsp?item=7"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://example.com/page;JSESSIONID=42"),
+            "https://example.com/page"
+        );
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://example.com/station?sid=42"),
+            "https://example.com/station?sid=42"
+        );
+    }
+
+    #[test]
+    fn trailing_slash() {
+        let add = canonicalizer(&["--trailing-slash", "add"]);
+        assert_eq!(
+            canonicalize(&add, "https://example.com/Kernel"),
+            "https://example.com/Kernel/"
+        );
+        assert_eq!(
+            canonicalize(&add, "https://example.com/Kernel/"),
+            "https://example.com/Kernel/"
+        );
+        assert_eq!(
+            canonicalize(&add, "https://example.com/style.css"),
+            "https://example.com/style.css"
+        );
+
+        let remove = canonicalizer(&["--trailing-slash", "remove"]);
+        assert_eq!(
+            canonicalize(&remove, "https://example.com/Kernel/"),
+            "https://example.com/Kernel"
+        );
+        assert_eq!(
+            canonicalize(&remove, "https://example.com/"),
+            "https://example.com/"
+        );
+
+        assert!("sometimes".parse::<TrailingSlash>().is_err());
+    }
+
+    #[test]
+    fn case_insensitive_paths() {
+        let canonicalizer = canonicalizer(&["--case-insensitive-paths"]);
+
+        assert_eq!(
+            canonicalize(&canonicalizer, "https://example.com/Docs/README.html?Q=A"),
+            "https://example.com/docs/readme.html?Q=A"
+        );
+    }
+}
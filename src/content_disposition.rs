@@ -0,0 +1,230 @@
+use percent_encoding::percent_decode_str;
+
+use super::url_helper::FILE_NAME_MAX_LENGTH;
+
+/// Parameters of a `Content-Disposition` header, as defined by RFC 6266
+#[derive(Debug, Default, PartialEq)]
+struct Parameters {
+    filename: Option<String>,
+    filename_ext: Option<String>,
+}
+
+/// Split a header value on `;`, ignoring the separators found in quoted strings
+fn split_parameters(header: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut in_quotes = false;
+    let mut escaped = false;
+    let mut start = 0;
+
+    for (i, c) in header.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if in_quotes => escaped = true,
+            '"' => in_quotes = !in_quotes,
+            ';' if !in_quotes => {
+                parts.push(&header[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    parts.push(&header[start..]);
+
+    parts
+}
+
+/// Remove the surrounding quotes and backslash escapes of a quoted-string
+fn unquote(value: &str) -> String {
+    let value = value.trim();
+    match value.strip_prefix('"') {
+        Some(inner) => {
+            let inner = inner.strip_suffix('"').unwrap_or(inner);
+            let mut unquoted = String::with_capacity(inner.len());
+            let mut chars = inner.chars();
+            while let Some(c) = chars.next() {
+                match c {
+                    '\\' => unquoted.extend(chars.next()),
+                    c => unquoted.push(c),
+                }
+            }
+            unquoted
+        }
+        None => value.to_string(),
+    }
+}
+
+/// Decode an RFC 8187 ext-value such as `UTF-8''na%C3%AFve.txt`
+fn decode_ext_value(value: &str) -> Option<String> {
+    let mut parts = value.trim().splitn(3, '\'');
+    let charset = parts.next()?;
+    let _language = parts.next()?;
+    let encoded = parts.next()?;
+
+    let bytes: Vec<u8> = percent_decode_str(encoded).collect();
+    let encoding = encoding_rs::Encoding::for_label(charset.as_bytes())?;
+    let (decoded, _, had_errors) = encoding.decode(&bytes);
+
+    if had_errors {
+        None
+    } else {
+        Some(decoded.into_owned())
+    }
+}
+
+/// Parse the parameters we care about, ignoring the disposition type and unknown ones
+fn parse(header: &str) -> Parameters {
+    let mut parameters = Parameters::default();
+
+    for part in split_parameters(header).into_iter().skip(1) {
+        let (name, value) = match part.split_once('=') {
+            Some((name, value)) => (name.trim().to_lowercase(), value),
+            None => continue,
+        };
+
+        match name.as_str() {
+            "filename" if parameters.filename.is_none() => {
+                parameters.filename = Some(unquote(value))
+            }
+            "filename*" if parameters.filename_ext.is_none() => {
+                parameters.filename_ext = decode_ext_value(value)
+            }
+            _ => (),
+        }
+    }
+
+    parameters
+}
+
+/// Truncate a file name to the maximum size supported by the file system, keeping its extension
+fn truncate(name: &str) -> String {
+    if name.len() <= FILE_NAME_MAX_LENGTH {
+        return name.to_string();
+    }
+
+    let (stem, extension) = match name.rfind('.') {
+        Some(index) if index > 0 && name.len() - index < FILE_NAME_MAX_LENGTH => {
+            name.split_at(index)
+        }
+        _ => (name, ""),
+    };
+
+    let mut end = FILE_NAME_MAX_LENGTH - extension.len();
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+
+    format!("{}{}", &stem[..end], extension)
+}
+
+/// Reduce a suggested file name to a single, harmless path component
+fn sanitize(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\']).next().unwrap_or("");
+    let name: String = name.chars().filter(|c| !c.is_control()).collect();
+    let name = name.trim();
+
+    match name {
+        "" | "." | ".." => None,
+        name => Some(truncate(name)),
+    }
+}
+
+/// Return the file name suggested by a `Content-Disposition` header, if it is usable.
+/// `filename*` is preferred over `filename`, and the result never contains a path separator
+pub fn filename(header: &str) -> Option<String> {
+    let parameters = parse(header);
+
+    parameters
+        .filename_ext
+        .as_deref()
+        .and_then(sanitize)
+        .or_else(|| parameters.filename.as_deref().and_then(sanitize))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filename_token() {
+        assert_eq!(
+            filename("attachment; filename=report.pdf"),
+            Some("report.pdf".to_string())
+        );
+    }
+
+    #[test]
+    fn filename_quoted() {
+        assert_eq!(
+            filename(r#"attachment; filename="annual report; 2023.pdf""#),
+            Some("annual report; 2023.pdf".to_string())
+        );
+        assert_eq!(
+            filename(r#"inline; filename="say \"hi\".txt""#),
+            Some("say \"hi\".txt".to_string())
+        );
+    }
+
+    #[test]
+    fn filename_ext_is_preferred() {
+        assert_eq!(
+            filename(
+                "attachment; filename=\"fallback.txt\"; filename*=UTF-8''na%C3%AFve%20file.txt"
+            ),
+            Some("naïve file.txt".to_string())
+        );
+        assert_eq!(
+            filename("attachment; filename*=iso-8859-1'en'%A3%20rates.txt"),
+            Some("£ rates.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn filename_ext_invalid_falls_back() {
+        assert_eq!(
+            filename("attachment; filename*=unknown''x.txt; filename=y.txt"),
+            Some("y.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn filename_missing() {
+        assert_eq!(filename("attachment"), None);
+        assert_eq!(filename("inline; size=42"), None);
+        assert_eq!(filename(""), None);
+    }
+
+    #[test]
+    fn filename_path_traversal() {
+        assert_eq!(
+            filename(r#"attachment; filename="../../.bashrc""#),
+            Some(".bashrc".to_string())
+        );
+        assert_eq!(
+            filename(r"attachment; filename=..\..\evil.exe"),
+            Some("evil.exe".to_string())
+        );
+        assert_eq!(filename(r#"attachment; filename="..""#), None);
+        assert_eq!(filename(r#"attachment; filename="dir/""#), None);
+        assert_eq!(
+            filename("attachment; filename*=UTF-8''%2E%2E%2Fetc%2Fpasswd"),
+            Some("passwd".to_string())
+        );
+    }
+
+    #[test]
+    fn filename_control_characters() {
+        assert_eq!(
+            filename("attachment; filename*=UTF-8''a%0Ab%00.txt"),
+            Some("ab.txt".to_string())
+        );
+    }
+
+    #[test]
+    fn filename_too_long() {
+        let name = format!("{}.pdf", "a".repeat(300));
+        let result = filename(&format!("attachment; filename={}", name)).unwrap();
+
+        assert_eq!(result.len(), FILE_NAME_MAX_LENGTH);
+        assert!(result.ends_with(".pdf"));
+    }
+}
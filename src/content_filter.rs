@@ -0,0 +1,273 @@
+use std::fmt;
+
+use reqwest::header::{HeaderMap, CONTENT_LENGTH, CONTENT_TYPE};
+
+use super::args;
+use super::mime_sniff;
+
+/// Media types of the pages, which are always downloaded so their links can be followed
+const HTML_TYPES: [&str; 2] = ["text/html", "application/xhtml+xml"];
+
+/// Why a download was rejected by the content filters
+#[derive(Debug, Clone, PartialEq)]
+pub enum Rejection {
+    /// The media type is not accepted, or is rejected
+    MediaType(String),
+    /// The body is smaller than the minimum size
+    TooSmall(u64),
+    /// The body is larger than the maximum size
+    TooLarge(u64),
+}
+
+impl fmt::Display for Rejection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rejection::MediaType(media_type) => write!(f, "its type {} is filtered", media_type),
+            Rejection::TooSmall(size) => write!(f, "its size of {} bytes is too small", size),
+            Rejection::TooLarge(size) => write!(f, "its size of {} bytes is too large", size),
+        }
+    }
+}
+
+/// Check if a media type matches a pattern, which can use a `*` wildcard as subtype or type
+fn matches_media_type(media_type: &str, pattern: &str) -> bool {
+    if pattern == "*" || pattern == "*/*" {
+        return true;
+    }
+
+    match pattern.split_once('/') {
+        Some((pattern_type, "*")) => media_type
+            .split_once('/')
+            .is_some_and(|(main_type, _)| main_type == pattern_type),
+        _ => media_type == pattern,
+    }
+}
+
+/// Filters the downloads from their `Content-Type` and size, as announced by the headers of the
+/// responses when possible, so unwanted bodies are not read
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContentFilter {
+    accept: Vec<String>,
+    reject: Vec<String>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+}
+
+impl ContentFilter {
+    /// Create a filter with the media types and sizes given on the command line
+    pub fn new(args: &args::Args) -> ContentFilter {
+        let lowercase = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| pattern.trim().to_lowercase())
+                .collect()
+        };
+
+        ContentFilter {
+            accept: lowercase(&args.accept_mime),
+            reject: lowercase(&args.reject_mime),
+            min_size: args.min_size,
+            max_size: args.max_size,
+        }
+    }
+
+    /// Return the media type of a response, without its parameters
+    fn media_type(headers: &HeaderMap) -> Option<String> {
+        let content_type = headers.get(CONTENT_TYPE)?.to_str().ok()?;
+        let media_type = content_type.split(';').next()?.trim().to_lowercase();
+
+        (!media_type.is_empty()).then_some(media_type)
+    }
+
+    /// Check if a response is a page, whose links are needed even when it is not saved
+    pub fn is_html(headers: &HeaderMap) -> bool {
+        ContentFilter::media_type(headers)
+            .is_some_and(|media_type| HTML_TYPES.contains(&media_type.as_str()))
+    }
+
+    /// Check if a media type is accepted and not rejected
+    pub fn check_media_type(&self, media_type: &str) -> Result<(), Rejection> {
+        let accepted = self.accept.is_empty()
+            || self
+                .accept
+                .iter()
+                .any(|pattern| matches_media_type(media_type, pattern));
+        let rejected = self
+            .reject
+            .iter()
+            .any(|pattern| matches_media_type(media_type, pattern));
+
+        if accepted && !rejected {
+            Ok(())
+        } else {
+            Err(Rejection::MediaType(media_type.to_string()))
+        }
+    }
+
+    /// Check if a size is within the limits
+    pub fn check_size(&self, size: u64) -> Result<(), Rejection> {
+        match (self.min_size, self.max_size) {
+            (Some(min_size), _) if size < min_size => Err(Rejection::TooSmall(size)),
+            (_, Some(max_size)) if size > max_size => Err(Rejection::TooLarge(size)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Check the headers of a response, before its body is read. Pages are only limited by the
+    /// maximum size, as their links are needed even if they are not saved. Responses without
+    /// a type or a length are not checked for them until their body is read
+    pub fn check_headers(&self, headers: &HeaderMap) -> Result<(), Rejection> {
+        let size = headers
+            .get(CONTENT_LENGTH)
+            .and_then(|length| length.to_str().ok())
+            .and_then(|length| length.parse::<u64>().ok());
+
+        if ContentFilter::is_html(headers) {
+            return match (size, self.max_size) {
+                (Some(size), Some(max_size)) if size > max_size => Err(Rejection::TooLarge(size)),
+                _ => Ok(()),
+            };
+        }
+
+        if let Some(media_type) = ContentFilter::media_type(headers) {
+            self.check_media_type(&media_type)?;
+        }
+        match size {
+            Some(size) => self.check_size(size),
+            None => Ok(()),
+        }
+    }
+
+    /// Check if a page of the given size is saved, its links being followed anyway
+    pub fn check_page(&self, size: u64) -> Result<(), Rejection> {
+        self.check_media_type(HTML_TYPES[0])?;
+        self.check_size(size)
+    }
+
+    /// Check a whole response, whose body is read. The type of a response sent without one is
+    /// sniffed from its body, so it can't get past the media types
+    pub fn check_response(&self, headers: &HeaderMap, body: &[u8]) -> Result<(), Rejection> {
+        self.check_headers(headers)?;
+
+        let is_html = match ContentFilter::media_type(headers) {
+            Some(_) => ContentFilter::is_html(headers),
+            None => {
+                let media_type = mime_sniff::sniff(body);
+                let is_html = HTML_TYPES.contains(&media_type);
+                if !is_html {
+                    self.check_media_type(media_type)?;
+                }
+                is_html
+            }
+        };
+
+        let size = body.len() as u64;
+        match self.max_size {
+            Some(max_size) if size > max_size => Err(Rejection::TooLarge(size)),
+            _ if is_html => Ok(()),
+            _ => self.check_size(size),
+        }
+    }
+
+    /// Return the maximum size of the bodies, beyond which their transfer is aborted
+    pub fn max_size(&self) -> Option<u64> {
+        self.max_size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use reqwest::header::HeaderValue;
+    use structopt::StructOpt;
+
+    use super::*;
+
+    fn filter(flags: &[&str]) -> ContentFilter {
+        let mut args = vec!["suckit", "https://example.com"];
+        args.extend_from_slice(flags);
+
+        ContentFilter::new(&args::Args::from_iter(args))
+    }
+
+    fn headers(content_type: &str, length: Option<u64>) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(CONTENT_TYPE, HeaderValue::from_str(content_type).unwrap());
+        if let Some(length) = length {
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(length));
+        }
+        headers
+    }
+
+    #[test]
+    fn media_types() {
+        let filter = filter(&[
+            "--accept-mime",
+            "image/*,application/pdf",
+            "--reject-mime",
+            "image/gif",
+        ]);
+
+        assert_eq!(filter.check_media_type("image/png"), Ok(()));
+        assert_eq!(filter.check_media_type("application/pdf"), Ok(()));
+        assert!(filter.check_media_type("image/gif").is_err());
+        assert!(filter.check_media_type("application/zip").is_err());
+        assert!(filter.check_media_type("imagex/png").is_err());
+
+        assert_eq!(
+            filter.check_headers(&headers("Application/PDF; name=a.pdf", None)),
+            Ok(())
+        );
+        assert_eq!(
+            filter.check_headers(&headers("text/plain", None)),
+            Err(Rejection::MediaType("text/plain".to_string()))
+        );
+        // Pages are needed for their links
+        assert_eq!(
+            filter.check_headers(&headers("text/html; charset=utf-8", None)),
+            Ok(())
+        );
+        assert_eq!(filter.check_headers(&HeaderMap::new()), Ok(()));
+
+        // The type of the untyped responses is sniffed once they are read
+        assert_eq!(
+            filter.check_response(&HeaderMap::new(), b"%PDF-1.7"),
+            Ok(())
+        );
+        assert_eq!(
+            filter.check_response(&HeaderMap::new(), b"Plain notes"),
+            Err(Rejection::MediaType("text/plain".to_string()))
+        );
+        assert_eq!(
+            filter.check_response(&HeaderMap::new(), b"<!DOCTYPE html><p>Page</p>"),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn sizes() {
+        let filter = filter(&["--min-size", "1K", "--max-size", "1M"]);
+
+        assert_eq!(
+            filter.check_headers(&headers("image/png", Some(10))),
+            Err(Rejection::TooSmall(10))
+        );
+        assert_eq!(
+            filter.check_headers(&headers("image/png", Some(2 << 20))),
+            Err(Rejection::TooLarge(2 << 20))
+        );
+        assert_eq!(filter.check_headers(&headers("image/png", None)), Ok(()));
+        assert_eq!(
+            filter.check_response(&headers("image/png", None), &[0; 10]),
+            Err(Rejection::TooSmall(10))
+        );
+
+        assert_eq!(
+            filter.check_response(&headers("text/html", None), &[0; 10]),
+            Ok(())
+        );
+        assert_eq!(
+            filter.check_response(&headers("text/html", None), &vec![0; 2 << 20]),
+            Err(Rejection::TooLarge(2 << 20))
+        );
+    }
+}
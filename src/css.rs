@@ -0,0 +1,69 @@
+use lazy_static::lazy_static;
+use regex::bytes::Regex;
+
+lazy_static! {
+    /// Matches the `url()` functions and the `@import` rules with a string, capturing the URL
+    /// in the group of its quoting style
+    static ref URL_REGEX: Regex = Regex::new(
+        r#"(?i)url\(\s*(?:"([^"]*)"|'([^']*)'|([^"'\s)][^\s)]*))\s*\)|@import\s+(?:"([^"]*)"|'([^']*)')"#
+    )
+    .unwrap();
+}
+
+/// Replace the URLs referenced by a stylesheet with the result of `rewrite`, leaving them
+/// untouched when it returns `None`. The stylesheet is handled as bytes, as its URLs are ASCII
+/// in all the encodings it can use
+pub fn rewrite_urls<F>(css: &[u8], mut rewrite: F) -> Vec<u8>
+where
+    F: FnMut(&str) -> Option<String>,
+{
+    let mut rewritten = Vec::with_capacity(css.len());
+    let mut last = 0;
+
+    for captures in URL_REGEX.captures_iter(css) {
+        let url = match captures.iter().skip(1).flatten().next() {
+            Some(url) => url,
+            None => continue,
+        };
+        let replacement = std::str::from_utf8(url.as_bytes())
+            .ok()
+            .filter(|url| !url.is_empty())
+            .and_then(&mut rewrite);
+
+        if let Some(replacement) = replacement {
+            rewritten.extend_from_slice(&css[last..url.start()]);
+            rewritten.extend_from_slice(replacement.as_bytes());
+            last = url.end();
+        }
+    }
+
+    rewritten.extend_from_slice(&css[last..]);
+    rewritten
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CSS: &str = r#"@import "reset.css";
+@import url('theme.css') screen;
+@font-face { font-family: Icons; src: url(fonts/icons.woff2) format("woff2"); }
+body { background: URL( "img/bg.png" ) no-repeat; }
+.empty { background: url(""); }"#;
+
+    #[test]
+    fn rewrite() {
+        let rewritten = rewrite_urls(CSS.as_bytes(), |url| {
+            (!url.starts_with("fonts")).then(|| format!("../{}", url))
+        });
+
+        assert_eq!(
+            String::from_utf8(rewritten).unwrap(),
+            r#"@import "../reset.css";
+@import url('../theme.css') screen;
+@font-face { font-family: Icons; src: url(fonts/icons.woff2) format("woff2"); }
+body { background: URL( "../img/bg.png" ) no-repeat; }
+.empty { background: url(""); }"#
+        );
+    }
+}
@@ -0,0 +1,103 @@
+use std::str::FromStr;
+
+use regex::Regex;
+use url::Url;
+
+/// How deep the pages matching a depth rule are visited
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DepthLimit {
+    /// Maximum depth of the pages, replacing `--depth`, -1 meaning infinity
+    Max(i32),
+    /// The pages are saved, but their links are not followed. Their requisites still are
+    NoFollow,
+}
+
+/// A rule giving its own depth limit to the pages whose URL matches a regex, like
+/// `/blog/page/=50` or `/tags/=nofollow`
+#[derive(Debug, Clone)]
+pub struct DepthRule {
+    pattern: Regex,
+    limit: DepthLimit,
+}
+
+impl FromStr for DepthRule {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<DepthRule, String> {
+        // The regex can contain '=' itself, not the limit
+        let (pattern, limit) = src.rsplit_once('=').ok_or_else(|| {
+            format!(
+                "Invalid depth rule {}, expected <regex>=<depth> or <regex>=nofollow",
+                src
+            )
+        })?;
+
+        let limit = match limit.trim() {
+            "nofollow" => DepthLimit::NoFollow,
+            depth => match depth.parse::<i32>() {
+                Ok(depth) if depth >= -1 => DepthLimit::Max(depth),
+                _ => {
+                    return Err(format!(
+                        "Invalid depth {} in rule {}, expected a depth, -1 or nofollow",
+                        depth, src
+                    ))
+                }
+            },
+        };
+        let pattern = Regex::new(pattern).map_err(|e| e.to_string())?;
+
+        Ok(DepthRule { pattern, limit })
+    }
+}
+
+/// Return the limit of the first rule matching an URL, if any
+pub fn find_limit(rules: &[DepthRule], url: &Url) -> Option<DepthLimit> {
+    rules
+        .iter()
+        .find(|rule| rule.pattern.is_match(url.as_str()))
+        .map(|rule| rule.limit)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let rule: DepthRule = "page=[0-9]+$=50".parse().unwrap();
+        assert_eq!(rule.pattern.as_str(), "page=[0-9]+$");
+        assert_eq!(rule.limit, DepthLimit::Max(50));
+        assert_eq!(
+            "/tags/=nofollow".parse::<DepthRule>().unwrap().limit,
+            DepthLimit::NoFollow
+        );
+        assert_eq!(
+            "/blog/=-1".parse::<DepthRule>().unwrap().limit,
+            DepthLimit::Max(-1)
+        );
+
+        assert!("/tags/".parse::<DepthRule>().is_err());
+        assert!("/tags/=deep".parse::<DepthRule>().is_err());
+        assert!("/tags/=-2".parse::<DepthRule>().is_err());
+        assert!("(=1".parse::<DepthRule>().is_err());
+    }
+
+    #[test]
+    fn first_match() {
+        let rules: Vec<DepthRule> = ["/blog/page/=50", "/blog/=nofollow"]
+            .iter()
+            .map(|rule| rule.parse().unwrap())
+            .collect();
+        let limit = |url| find_limit(&rules, &Url::parse(url).unwrap());
+
+        assert_eq!(
+            limit("https://example.com/blog/page/3"),
+            Some(DepthLimit::Max(50))
+        );
+        assert_eq!(
+            limit("https://example.com/blog/post.html"),
+            Some(DepthLimit::NoFollow)
+        );
+        assert_eq!(limit("https://example.com/"), None);
+    }
+}
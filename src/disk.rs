@@ -39,11 +39,8 @@ pub fn symlink(source: &str, destination: &str, path: &Option<PathBuf>) {
     };
 
     if let Some(parent) = source.parent() {
-        match fs::create_dir_all(parent) {
-            Err(err) => {
-                error!("Couldn't create folder {}: {}", parent.display(), err);
-            }
-            Ok(()) => (),
+        if let Err(err) = fs::create_dir_all(parent) {
+            error!("Couldn't create folder {}: {}", parent.display(), err);
         }
     }
 
@@ -52,7 +49,7 @@ pub fn symlink(source: &str, destination: &str, path: &Option<PathBuf>) {
         None => PathBuf::from(destination),
     };
 
-    let target = pathdiff::diff_paths(&destination, &source.parent().unwrap()).unwrap();
+    let target = pathdiff::diff_paths(&destination, source.parent().unwrap()).unwrap();
 
     if let Err(err) = symlink_file(&target, &source) {
         warn!(
@@ -7,6 +7,59 @@ use crate::error;
 static CSS_SELECTORS: &str = "[src],[href]";
 static CSS_ATTRIBUTES: [&str; 2] = ["src", "href"];
 
+/// Elements whose text is not displayed
+static HIDDEN_ELEMENTS: [&str; 4] = ["script", "style", "noscript", "template"];
+
+/// Elements whose sources are needed to display the page
+static REQUISITE_ELEMENTS: [&str; 8] = [
+    "img", "script", "source", "video", "audio", "track", "embed", "input",
+];
+
+/// Relations of the `link` element pointing to resources of the page, like stylesheets
+static REQUISITE_RELATIONS: [&str; 7] = [
+    "stylesheet",
+    "icon",
+    "apple-touch-icon",
+    "preload",
+    "modulepreload",
+    "manifest",
+    "mask-icon",
+];
+
+///What a link is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkKind {
+    ///A page the user can go to, like the target of an anchor
+    Navigation,
+    ///A resource needed to display the page, like images, stylesheets and scripts
+    Requisite,
+    ///A page displayed inside the page, by an `iframe` or a `frame`
+    Frame,
+}
+
+impl LinkKind {
+    ///Classify a link from its element and the `rel` attribute of `link` elements
+    fn classify(element: &str, rel: Option<&str>) -> LinkKind {
+        if matches!(element, "iframe" | "frame") {
+            return LinkKind::Frame;
+        }
+
+        let is_requisite = match element {
+            "link" => rel.is_some_and(|rel| {
+                rel.split_ascii_whitespace()
+                    .any(|relation| REQUISITE_RELATIONS.contains(&relation.to_lowercase().as_str()))
+            }),
+            element => REQUISITE_ELEMENTS.contains(&element),
+        };
+
+        if is_requisite {
+            LinkKind::Requisite
+        } else {
+            LinkKind::Navigation
+        }
+    }
+}
+
 ///Struct containing a dom tree of a web page
 pub struct Dom {
     tree: kuchiki::NodeRef,
@@ -31,20 +84,75 @@ impl Dom {
         String::from_utf8(vec).unwrap()
     }
 
+    ///Returns the text displayed by the page, without the content of scripts and styles
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+
+        for node in self.tree.descendants() {
+            let hidden = node.parent().is_some_and(|parent| {
+                parent
+                    .as_element()
+                    .is_some_and(|element| HIDDEN_ELEMENTS.contains(&&*element.name.local))
+            });
+            if let Some(content) = node.as_text().filter(|_| !hidden) {
+                text.push_str(&content.borrow());
+                text.push(' ');
+            }
+        }
+
+        text
+    }
+
     ///Returns all urls in the dom tree
+    #[allow(clippy::mut_from_ref)]
     pub fn find_urls_as_strings(&self) -> Vec<&mut String> {
+        self.find_links().into_iter().map(|(_, url)| url).collect()
+    }
+
+    ///Returns the stylesheets embedded in the dom tree, from `style` elements and attributes
+    #[allow(clippy::mut_from_ref)]
+    pub fn find_styles(&self) -> Vec<&mut String> {
         let mut vec: Vec<&mut String> = Vec::new();
 
+        for node in self.tree.descendants() {
+            if let Some(element) = node.as_element() {
+                let attributes = element.attributes.as_ptr();
+                if let Some(style) = unsafe { (*attributes).get_mut("style") } {
+                    vec.push(style);
+                }
+            } else if let Some(text) = node.as_text() {
+                let in_style = node.parent().is_some_and(|parent| {
+                    parent
+                        .as_element()
+                        .is_some_and(|element| &*element.name.local == "style")
+                });
+                if in_style {
+                    vec.push(unsafe { &mut *text.as_ptr() });
+                }
+            }
+        }
+
+        vec
+    }
+
+    ///Returns all urls in the dom tree, with what they are used for
+    #[allow(clippy::mut_from_ref)]
+    pub fn find_links(&self) -> Vec<(LinkKind, &mut String)> {
+        let mut vec: Vec<(LinkKind, &mut String)> = Vec::new();
+
         let nodes = match self.tree.select(CSS_SELECTORS) {
             Ok(nodes) => nodes,
             Err(_) => return vec,
         };
 
         for node in nodes {
-            let attributes = node.deref().attributes.as_ptr();
+            let element = node.deref();
+            let kind =
+                LinkKind::classify(&element.name.local, element.attributes.borrow().get("rel"));
+            let attributes = element.attributes.as_ptr();
             for attribute in CSS_ATTRIBUTES.iter() {
                 if let Some(url) = unsafe { (*attributes).get_mut(*attribute) } {
-                    vec.push(url);
+                    vec.push((kind, url));
                 }
             }
         }
@@ -62,6 +170,19 @@ mod tests {
         let _dom = Dom::new("<html></html>");
     }
 
+    #[test]
+    fn text() {
+        let dom = Dom::new(
+            "<html><head><title>Title</title><style>p { color: red; }</style></head>
+            <body><p>Some <b>bold</b> text</p><script>alert(1)</script></body></html>",
+        );
+
+        assert_eq!(
+            dom.text().split_whitespace().collect::<Vec<&str>>(),
+            vec!["Title", "Some", "bold", "text"]
+        );
+    }
+
     #[test]
     fn find_urls_as_strings() {
         let url1 = "https://upload.wikimedia.org/wikipedia/commons/thumb/3/34/Anser_anser_1_%28Piotr_Kuczynski%29.jpg/800px-Anser_anser_1_%28Piotr_Kuczynski%29.jpg";
@@ -73,4 +194,47 @@ mod tests {
         assert_eq!(vec[0], url1);
         assert_eq!(vec[1], url2);
     }
+
+    #[test]
+    fn find_styles() {
+        let dom = Dom::new(
+            r#"<style>body { background: url(bg.png); }</style>
+            <div style="background: url('tile.png')">Text</div>"#,
+        );
+        let styles: Vec<String> = dom.find_styles().into_iter().map(|s| s.clone()).collect();
+
+        assert_eq!(
+            styles,
+            vec![
+                "body { background: url(bg.png); }".to_string(),
+                "background: url('tile.png')".to_string(),
+            ]
+        );
+    }
+
+    #[test]
+    fn find_links() {
+        let dom = Dom::new(
+            r#"<link rel="stylesheet" href="style.css"><link rel="next" href="page2.html">
+            <a href="about.html">About</a><img src="logo.png"><script src="app.js"></script>
+            <iframe src="video.html"></iframe>"#,
+        );
+        let links: Vec<(LinkKind, String)> = dom
+            .find_links()
+            .into_iter()
+            .map(|(kind, url)| (kind, url.clone()))
+            .collect();
+
+        assert_eq!(
+            links,
+            vec![
+                (LinkKind::Requisite, "style.css".to_string()),
+                (LinkKind::Navigation, "page2.html".to_string()),
+                (LinkKind::Navigation, "about.html".to_string()),
+                (LinkKind::Requisite, "logo.png".to_string()),
+                (LinkKind::Requisite, "app.js".to_string()),
+                (LinkKind::Frame, "video.html".to_string()),
+            ]
+        );
+    }
 }
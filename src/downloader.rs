@@ -1,20 +1,145 @@
 use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::io::{self, Read};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use lazy_static::lazy_static;
 use regex::Regex;
-use reqwest::header::{HeaderMap, HeaderValue, COOKIE};
+use reqwest::cookie::Jar;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT_ENCODING, CONTENT_ENCODING, COOKIE};
 use url::Url;
 
-use crate::warn;
+use crate::{error, warn};
 
+use super::args;
+use super::budget::{Budget, Exhausted};
+use super::cache::{self, Cache, CachedResponse};
+use super::content_disposition;
+use super::content_filter::{ContentFilter, Rejection};
+use super::mime_sniff;
+use super::network_policy::{NetworkPolicy, RefusedAddress};
+use super::rate_limit::RateLimiter;
+use super::resolver::Resolver;
 use super::response::{Response, ResponseData};
 
 const AUTH_CHUNK_SIZE: usize = 3;
 
+/// Extensions of files served compressed on purpose. Their `Content-Encoding` is not
+/// decoded, so they are stored as the server intended them to be downloaded
+const PRECOMPRESSED_EXTENSIONS: [&str; 6] = ["gz", "tgz", "svgz", "br", "zst", "zstd"];
+
+/// Size of the chunks in which response bodies are read
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+
+/// Maximum number of redirections followed for a single download, like reqwest's default
+const MAX_REDIRECTIONS: usize = 10;
+
+/// Errors which can happen while downloading a page
+#[derive(Debug)]
+pub enum DownloadError {
+    /// The request couldn't be sent or the server didn't answer properly
+    Http(reqwest::Error),
+    /// The body of the response couldn't be read
+    Io(io::Error),
+    /// The download took longer than the request timeout
+    RequestTimeout(Duration),
+    /// The network policy refused the address of the server
+    Refused(RefusedAddress),
+    /// The page is not in the cache, and the network can't be used
+    NotCached,
+    /// The content filters rejected the response
+    Rejected(Rejection),
+    /// A budget was exhausted during the download
+    Aborted(Exhausted),
+}
+
+impl DownloadError {
+    /// If the error is due to one of the timeouts
+    pub fn is_timeout(&self) -> bool {
+        match self {
+            DownloadError::Http(e) => e.is_timeout(),
+            DownloadError::Io(e) => e.kind() == io::ErrorKind::TimedOut,
+            DownloadError::RequestTimeout(_) => true,
+            DownloadError::Refused(_)
+            | DownloadError::NotCached
+            | DownloadError::Rejected(_)
+            | DownloadError::Aborted(_) => false,
+        }
+    }
+
+    /// If the error is due to the network policy
+    pub fn is_refused(&self) -> bool {
+        matches!(self, DownloadError::Refused(_))
+    }
+}
+
+impl fmt::Display for DownloadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DownloadError::Http(e) => write!(f, "{}", e),
+            DownloadError::Io(e) => write!(f, "couldn't read the response: {}", e),
+            DownloadError::RequestTimeout(timeout) => {
+                write!(f, "request took longer than {}s", timeout.as_secs())
+            }
+            DownloadError::Refused(refused) => write!(f, "{}", refused),
+            DownloadError::NotCached => write!(f, "not in the cache"),
+            DownloadError::Rejected(rejection) => write!(f, "{}", rejection),
+            DownloadError::Aborted(exhausted) => write!(f, "{}", exhausted),
+        }
+    }
+}
+
+impl Error for DownloadError {}
+
+impl From<reqwest::Error> for DownloadError {
+    fn from(e: reqwest::Error) -> DownloadError {
+        // Refusals from the resolver or the redirect policy are buried in the error sources
+        let mut source = e.source();
+        while let Some(inner) = source {
+            if let Some(refused) = inner.downcast_ref::<RefusedAddress>() {
+                return DownloadError::Refused(refused.clone());
+            }
+            source = inner.source();
+        }
+
+        DownloadError::Http(e)
+    }
+}
+
+impl From<io::Error> for DownloadError {
+    fn from(e: io::Error) -> DownloadError {
+        // The blocking client wraps its own errors, including timeouts, in io::Error
+        match e
+            .get_ref()
+            .and_then(|inner| inner.downcast_ref::<reqwest::Error>())
+        {
+            Some(_) => DownloadError::from(
+                *e.into_inner()
+                    .unwrap()
+                    .downcast::<reqwest::Error>()
+                    .unwrap(),
+            ),
+            None => DownloadError::Io(e),
+        }
+    }
+}
+
 ///A Downloader to download web content
 pub struct Downloader {
     client: reqwest::blocking::Client,
+    raw_client: reqwest::blocking::Client,
     tries: usize,
+    request_timeout: Option<Duration>,
+    network_policy: Option<NetworkPolicy>,
+    rate_limiter: RateLimiter,
+    content_filter: ContentFilter,
+    cache: Option<Cache>,
+    offline: bool,
+    /// The budget aborting the downloads in progress once exhausted, if any
+    budget: Option<Arc<Budget>>,
     auth_map: HashMap<String, (String, Option<String>)>,
 }
 
@@ -51,152 +176,461 @@ fn parse_auth(auth: &[String], origin: &Url) -> Result<(String, Option<String>,
     }
 }
 
+/// Follow the redirections allowed by the network policy, if any
+fn redirect_policy(network_policy: &Option<NetworkPolicy>) -> reqwest::redirect::Policy {
+    let network_policy = match network_policy {
+        Some(network_policy) => network_policy.clone(),
+        None => return reqwest::redirect::Policy::limited(MAX_REDIRECTIONS),
+    };
+
+    reqwest::redirect::Policy::custom(move |attempt| {
+        match network_policy.check_url(attempt.url()) {
+            Err(refused) => attempt.error(refused),
+            Ok(()) if attempt.previous().len() >= MAX_REDIRECTIONS => {
+                attempt.error("too many redirects")
+            }
+            Ok(()) => attempt.follow(),
+        }
+    })
+}
+
 impl Downloader {
-    /// Create a new Downloader
-    pub fn new(
-        tries: usize,
-        user_agent: &str,
-        cookie: &str,
-        disable_certs_checks: bool,
-        auth: &[String],
-        origin: &Url,
-    ) -> Downloader {
+    /// Create a new Downloader from the command line options
+    pub fn new(args: &args::Args) -> Downloader {
         // Create a mapping of hosts to username, password tuples for authentication
         let mut auth_map = HashMap::new();
         // Iterate over the auth string in chunks of 3 items each for (username, password, host)
-        for auth_chunk in auth.chunks(AUTH_CHUNK_SIZE) {
+        for auth_chunk in args.auth.chunks(AUTH_CHUNK_SIZE) {
             // Throwing the error with panic! for now if parsing fails
-            let (username, password, host) = parse_auth(auth_chunk, origin).unwrap();
+            let (username, password, host) = parse_auth(auth_chunk, &args.origin).unwrap();
             auth_map.insert(host, (username, password));
         }
 
         let mut headers = HeaderMap::new();
-        headers.insert(COOKIE, HeaderValue::from_str(cookie).unwrap());
+        headers.insert(COOKIE, HeaderValue::from_str(&args.cookie).unwrap());
+
+        let connect_timeout = args.connect_timeout.map(Duration::from_secs);
+        let request_timeout = args.request_timeout.map(Duration::from_secs);
+        // The read timeout of the blocking client also applies while waiting for the headers.
+        // Without it, waiting for them would only be limited by the request timeout
+        let read_timeout = match args.read_timeout {
+            0 => request_timeout,
+            read_timeout => Some(Duration::from_secs(read_timeout)),
+        };
+
+        let network_policy = if args.deny_private_networks {
+            Some(NetworkPolicy::new(&args.allow_network))
+        } else {
+            None
+        };
+
+        // The system resolver is enough unless some hosts are overridden or filtered
+        let resolver = if args.resolve.is_empty() && network_policy.is_none() {
+            None
+        } else {
+            Some(Arc::new(Resolver::new(
+                &args.resolve,
+                network_policy.clone(),
+            )))
+        };
+
+        let cache = args.cache_dir.as_ref().map(|dir| {
+            Cache::new(dir)
+                .unwrap_or_else(|e| error!("Couldn't create the cache in {}: {}", dir.display(), e))
+        });
+
+        // Both clients share their cookies
+        let cookie_jar = Arc::new(Jar::default());
+        let client_builder = || {
+            let builder = reqwest::blocking::ClientBuilder::new()
+                .default_headers(headers.clone())
+                .danger_accept_invalid_certs(args.disable_certs_checks)
+                .cookie_provider(cookie_jar.clone())
+                .user_agent(&args.user_agent)
+                .connect_timeout(connect_timeout)
+                .timeout(read_timeout)
+                .redirect(redirect_policy(&network_policy));
+
+            let builder = match &resolver {
+                Some(resolver) => builder.dns_resolver(resolver.clone()),
+                None => builder,
+            };
+
+            // A proxy resolves the hosts itself, out of reach of the network policy, so the
+            // proxies of the environment would let any address through
+            if network_policy.is_some() {
+                builder.no_proxy()
+            } else {
+                builder
+            }
+        };
+
+        let mut identity_headers = HeaderMap::new();
+        identity_headers.insert(ACCEPT_ENCODING, HeaderValue::from_static("identity"));
 
         Downloader {
-            client: reqwest::blocking::ClientBuilder::new()
-                .default_headers(headers)
-                .danger_accept_invalid_certs(disable_certs_checks)
-                .cookie_store(true)
-                .user_agent(user_agent)
+            client: client_builder()
+                .gzip(true)
+                .brotli(true)
+                .deflate(true)
+                .zstd(true)
+                .build()
+                .unwrap(),
+            raw_client: client_builder()
+                .default_headers(identity_headers)
+                .no_gzip()
+                .no_brotli()
+                .no_deflate()
+                .no_zstd()
                 .build()
                 .unwrap(),
-            tries,
+            tries: args.tries,
+            request_timeout,
+            network_policy,
+            rate_limiter: RateLimiter::new(args.limit_rate, args.limit_rate_per_host),
+            content_filter: ContentFilter::new(args),
+            cache,
+            offline: args.offline,
+            budget: None,
             auth_map,
         }
     }
 
+    /// Abort the downloads in progress once `budget` is exhausted
+    pub fn with_budget(mut self, budget: Arc<Budget>) -> Downloader {
+        self.budget = Some(budget);
+        self
+    }
+
+    ///Check if the url points to a file which is compressed on purpose, like an archive
+    fn is_precompressed(url: &Url) -> bool {
+        let extension = url
+            .path_segments()
+            .and_then(|mut segments| segments.next_back())
+            .and_then(|filename| Path::new(filename).extension())
+            .and_then(|extension| extension.to_str())
+            .map(|extension| extension.to_lowercase());
+
+        matches!(extension, Some(extension) if PRECOMPRESSED_EXTENSIONS.contains(&extension.as_str()))
+    }
+
+    ///Check if the body is still encoded, which happens when we asked not to decode it
+    fn is_encoded(header_map: &HeaderMap) -> bool {
+        header_map
+            .get(CONTENT_ENCODING)
+            .is_some_and(|encoding| !encoding.as_bytes().eq_ignore_ascii_case(b"identity"))
+    }
+
     ///Check if the type in the 'content-type' head field is html
     fn is_html(content_type: &str) -> bool {
         content_type.contains("text/html")
     }
 
-    ///Return the filename based on the HTML header of the response
+    ///Return the sanitized filename based on the HTML header of the response
     fn get_filename(header_map: &reqwest::header::HeaderMap) -> Option<String> {
-        if let Some(content_disposition) = header_map.get("content-disposition") {
-            let content_disposition = content_disposition.to_str().unwrap();
-            let index = content_disposition.find('=').unwrap() + 1;
+        let content_disposition = header_map.get("content-disposition")?;
 
-            Some(content_disposition[index..].to_string())
-        } else {
-            None
-        }
+        // Header values are not always valid UTF-8, RFC 6266 asks to read them as ISO-8859-1
+        let (content_disposition, _) =
+            encoding_rs::WINDOWS_1252.decode_without_bom_handling(content_disposition.as_bytes());
+
+        content_disposition::filename(&content_disposition)
     }
 
     /// Load HTTP auth credentials in a username, password tuple based on the host string
     fn get_auth(&self, url: &Url) -> Option<&(String, Option<String>)> {
         if let Some(host) = url.host_str() {
-            self.auth_map.get(&host.to_string())
+            self.auth_map.get(host)
         } else {
             None
         }
     }
 
-    ///Download the content at this url
-    fn make_request(&self, url: &Url) -> Result<Response, reqwest::Error> {
-        let req = self.client.get(url.clone());
+    ///Fail if the deadline given by the request timeout is reached
+    fn check_deadline(&self, deadline: Option<Instant>) -> Result<(), DownloadError> {
+        match (deadline, self.request_timeout) {
+            (Some(deadline), Some(timeout)) if Instant::now() >= deadline => {
+                Err(DownloadError::RequestTimeout(timeout))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    ///Fail if the budget was exhausted during the download of `url`, counting the `pending`
+    ///bytes read so far. They are accounted for when the download is aborted, as they were
+    ///transferred anyway
+    fn check_budget(&self, url: &Url, pending: usize) -> Result<(), DownloadError> {
+        let budget = match &self.budget {
+            Some(budget) => budget,
+            None => return Ok(()),
+        };
+
+        budget
+            .check_transfer(url, pending as u64)
+            .map_err(|exhausted| {
+                budget.add_bytes(url, pending as u64);
+                DownloadError::Aborted(exhausted)
+            })
+    }
+
+    ///Read the body of the response to `url` within the rate limits, giving up if the deadline
+    ///is reached, if the budget is exhausted or if it is larger than the maximum size
+    fn read_body(
+        &self,
+        url: &Url,
+        response: &mut reqwest::blocking::Response,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<u8>, DownloadError> {
+        let host = response.url().host_str().map(str::to_string);
+        // Smaller chunks keep the speed steady when it is limited
+        let chunk_size = match self.rate_limiter.min_rate() {
+            Some(rate) => READ_CHUNK_SIZE.min(rate as usize),
+            None => READ_CHUNK_SIZE,
+        };
+        let mut body = Vec::new();
+        let mut chunk = vec![0; chunk_size];
+
+        loop {
+            self.check_deadline(deadline)?;
+            self.check_budget(url, body.len())?;
+
+            match response.read(&mut chunk) {
+                Ok(0) => return Ok(body),
+                Ok(size) => {
+                    body.extend_from_slice(&chunk[..size]);
+                    self.rate_limiter.throttle(host.as_deref(), size);
+
+                    let length = body.len() as u64;
+                    if self
+                        .content_filter
+                        .max_size()
+                        .is_some_and(|max| length > max)
+                    {
+                        return Err(DownloadError::Rejected(Rejection::TooLarge(length)));
+                    }
+                }
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => (),
+                Err(e) => return Err(e.into()),
+            }
+        }
+    }
+
+    ///Build the response of the scraper from the status, the headers and the body sent by the server
+    fn to_response(status: u16, headers: &HeaderMap, raw_data: Vec<u8>) -> Response {
+        lazy_static! {
+            static ref DATA_TYPE_REGEX: Regex =
+                Regex::new(r#"^.*(\b[a-z]+/[a-z-+\.]+).*$"#).unwrap();
+            static ref CHARSET_REGEX: Regex =
+                Regex::new(r#"^.*charset\s*=\s*["']?([^"'\s;]+).*$"#).unwrap();
+        }
+
+        let (data_type, charset): (Option<String>, Option<String>) =
+            match headers.get("content-type") {
+                Some(content_type_header) => {
+                    let content_type = content_type_header.to_str().unwrap();
+                    let data_type_captures = DATA_TYPE_REGEX.captures_iter(content_type).next();
+                    let data_type = data_type_captures
+                        .map(|first| first.get(1).unwrap().as_str().to_lowercase());
+                    let charset_captures = CHARSET_REGEX.captures_iter(content_type).next();
+                    let charset =
+                        charset_captures.map(|first| first.get(1).unwrap().as_str().to_lowercase());
+                    (data_type, charset)
+                }
+                None => (None, None),
+            };
+        let no_sniff = headers
+            .get("x-content-type-options")
+            .is_some_and(|value| value.as_bytes().eq_ignore_ascii_case(b"nosniff"));
+
+        let filename = Downloader::get_filename(headers);
+
+        let encoded = Downloader::is_encoded(headers);
+
+        // Only trust the server if it sent a meaningful type, otherwise look at the data
+        let data_type = match data_type {
+            Some(data_type) if no_sniff || !mime_sniff::is_unknown(&data_type) => data_type,
+            _ => mime_sniff::sniff(&raw_data).to_string(),
+        };
+
+        // A compressed page can't be parsed, it is saved as is
+        let (response_data, filename) = if Downloader::is_html(&data_type) && !encoded {
+            (ResponseData::Html(raw_data), None)
+        } else if data_type == "text/css" && !encoded {
+            (ResponseData::Css(raw_data), filename)
+        } else {
+            (ResponseData::Other(raw_data), filename)
+        };
+
+        Response::new(response_data, filename, charset, status)
+    }
+
+    ///Download the content at this url, sending the given extra headers
+    fn make_request(
+        &self,
+        url: &Url,
+        extra_headers: &HeaderMap,
+        deadline: Option<Instant>,
+    ) -> Result<(u16, HeaderMap, Vec<u8>), DownloadError> {
+        // Addresses written in the URL never reach the resolver
+        if let Some(network_policy) = &self.network_policy {
+            network_policy
+                .check_url(url)
+                .map_err(DownloadError::Refused)?;
+        }
+
+        let client = if Downloader::is_precompressed(url) {
+            &self.raw_client
+        } else {
+            &self.client
+        };
+        let req = client.get(url.clone()).headers(extra_headers.clone());
         let req = match self.get_auth(url) {
             Some((username, password)) => req.basic_auth(username, password.clone()),
             None => req,
         };
         match req.send() {
             Ok(mut data) => {
-                lazy_static! {
-                    static ref DATA_TYPE_REGEX: Regex =
-                        Regex::new(r#"^.*(\b[a-z]+/[a-z-+\.]+).*$"#).unwrap();
-                    static ref CHARSET_REGEX: Regex =
-                        Regex::new(r#"^.*charset\s*=\s*["']?([^"'\s;]+).*$"#).unwrap();
+                // Unwanted bodies are not read, dropping the response aborts the transfer
+                if data.status().is_success() {
+                    self.content_filter
+                        .check_headers(data.headers())
+                        .map_err(DownloadError::Rejected)?;
                 }
+                let raw_data = self.read_body(url, &mut data, deadline)?;
 
-                let (data_type, charset): (String, Option<String>) =
-                    match data.headers().get("content-type") {
-                        Some(content_type_header) => {
-                            let content_type = content_type_header.to_str().unwrap();
-                            let data_type_captures =
-                                DATA_TYPE_REGEX.captures_iter(content_type).next();
-                            let data_type = data_type_captures
-                                .map_or(String::from("text/html"), |first| {
-                                    first.get(1).unwrap().as_str().to_lowercase()
-                                });
-                            let charset_captures = CHARSET_REGEX.captures_iter(content_type).next();
-                            let charset = charset_captures
-                                .map(|first| first.get(1).unwrap().as_str().to_lowercase());
-                            (data_type, charset)
-                        }
-                        None => (String::from("text/html"), None),
-                    };
-
-                let filename = if !Downloader::is_html(&data_type) {
-                    Downloader::get_filename(data.headers())
-                } else {
-                    None
-                };
-
-                let mut raw_data: Vec<u8> = Vec::new();
-                data.copy_to(&mut raw_data).unwrap();
-                let response_data = if Downloader::is_html(&data_type) {
-                    ResponseData::Html(raw_data)
-                } else {
-                    ResponseData::Other(raw_data)
-                };
-
-                Ok(Response::new(response_data, filename, charset))
+                Ok((data.status().as_u16(), data.headers().clone(), raw_data))
             }
 
             Err(e) => {
-                warn!("Downloader.get() has encountered an error: {}", e);
+                let e = DownloadError::from(e);
+                if e.is_timeout() {
+                    warn!("Downloader.get() has timed out: {}", e);
+                } else if !e.is_refused() {
+                    warn!("Downloader.get() has encountered an error: {}", e);
+                }
                 Err(e)
             }
         }
     }
 
-    ///Download the content of an url and retries at most 'tries' times on failure
-    pub fn get(&self, url: &Url) -> Result<Response, reqwest::Error> {
-        let mut error: Option<reqwest::Error> = None;
+    ///Download the content of an url and retries at most 'tries' times on failure.
+    ///All the tries must fit in the request timeout
+    fn fetch(
+        &self,
+        url: &Url,
+        extra_headers: &HeaderMap,
+    ) -> Result<(u16, HeaderMap, Vec<u8>), DownloadError> {
+        let deadline = self.request_timeout.map(|timeout| Instant::now() + timeout);
+
+        let mut error: Option<DownloadError> = None;
         for _ in 0..self.tries {
-            match self.make_request(url) {
+            self.check_deadline(deadline)?;
+            self.check_budget(url, 0)?;
+
+            match self.make_request(url, extra_headers, deadline) {
                 Ok(response) => return Ok(response),
+                // Trying again would be refused again
+                Err(e)
+                    if e.is_refused()
+                        || matches!(e, DownloadError::Rejected(_) | DownloadError::Aborted(_)) =>
+                {
+                    return Err(e)
+                }
                 Err(e) => error = Some(e),
             }
         }
 
         Err(error.unwrap())
     }
+
+    ///Check a successful response against the content filters, once its body is read
+    fn check_content(&self, response: &CachedResponse) -> Result<(), DownloadError> {
+        if !(200..300).contains(&response.status) {
+            return Ok(());
+        }
+
+        self.content_filter
+            .check_response(&response.headers, &response.body)
+            .map_err(DownloadError::Rejected)
+    }
+
+    ///Get the content of an url from the cache while it is fresh, from the server otherwise
+    pub fn get(&self, url: &Url) -> Result<Response, DownloadError> {
+        let cached = self.cache.as_ref().and_then(|cache| cache.load(url));
+        match &cached {
+            Some(cached) if self.offline || cached.is_fresh(cache::now()) => {
+                self.check_content(cached)?;
+                return Ok(Downloader::to_response(
+                    cached.status,
+                    &cached.headers,
+                    cached.body.clone(),
+                ));
+            }
+            None if self.offline => return Err(DownloadError::NotCached),
+            _ => (),
+        }
+
+        // A stale response can still be used if the server says it didn't change
+        let validators = cached
+            .as_ref()
+            .map_or_else(HeaderMap::new, CachedResponse::validators);
+        let (status, headers, body) = self.fetch(url, &validators)?;
+
+        let response = match cached {
+            Some(mut cached) if status == 304 => {
+                cached.refresh(&headers, cache::now());
+                cached
+            }
+            _ => CachedResponse {
+                status,
+                headers,
+                body,
+                stored_at: cache::now(),
+            },
+        };
+
+        if let Some(cache) = &self.cache {
+            if CachedResponse::is_storable(response.status, &response.headers) {
+                if let Err(e) = cache.store(url, &response) {
+                    warn!("Couldn't store {} in the cache: {}", url, e);
+                }
+            }
+        }
+
+        self.check_content(&response)?;
+        Ok(Downloader::to_response(
+            response.status,
+            &response.headers,
+            response.body,
+        ))
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use structopt::StructOpt;
+
     use super::*;
 
     #[test]
     fn test_download_url() {
-        let url: Url = Url::parse("https://lwn.net").unwrap();
-        match Downloader::new(1, "suckit", "", false, &[], &url).get(&url) {
-            Err(e) => assert!(false, "Fail to download lwn.net: {:?}", e),
-            _ => {}
+        let args = args::Args::from_iter(["suckit", "https://lwn.net", "--tries", "1"]);
+        if let Err(e) = Downloader::new(&args).get(&args.origin) {
+            panic!("Fail to download lwn.net: {:?}", e)
         }
     }
 
+    #[test]
+    fn test_is_precompressed() {
+        let precompressed = |url| Downloader::is_precompressed(&Url::parse(url).unwrap());
+
+        assert!(precompressed("https://example.com/linux-6.1.tar.gz"));
+        assert!(precompressed("https://example.com/dump.SQL.ZST?version=2"));
+        assert!(!precompressed("https://example.com/index.html"));
+        assert!(!precompressed("https://example.com/gz/"));
+        assert!(!precompressed("https://example.com/"));
+    }
+
     #[test]
     fn test_parse_auth() {
         assert_eq!(
@@ -0,0 +1,284 @@
+use std::fs;
+use std::path::Path;
+
+use regex::{Regex, RegexSet};
+use url::Url;
+
+/// Start of the regexes matching a glob against the path of an URL: the scheme and the host
+const GLOB_URL_PREFIX: &str = r"^[a-zA-Z][a-zA-Z0-9+.-]*://[^/?#]*";
+
+/// End of the regexes matching a glob against the path of an URL: the query and the fragment
+const GLOB_URL_SUFFIX: &str = r"(?:[?#].*)?$";
+
+/// Translate a glob into a regex matching the URLs whose path matches it. `*` matches within
+/// a path segment and `**` across segments. Globs not starting with a `/` match the end of the
+/// path, like `*.pdf` matching the file name
+pub fn glob_to_regex(glob: &str) -> Result<String, String> {
+    let mut regex = String::from(GLOB_URL_PREFIX);
+    // Relative globs can match in any directory
+    if !glob.starts_with('/') {
+        regex.push_str("(?:/[^?#]*)?/");
+    }
+
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                regex.push_str("[^?#]*");
+            }
+            '*' => regex.push_str("[^/?#]*"),
+            '?' => regex.push_str("[^/?#]"),
+            '[' => {
+                regex.push('[');
+                if chars.peek() == Some(&'!') {
+                    chars.next();
+                    regex.push('^');
+                }
+                loop {
+                    match chars.next() {
+                        Some(']') => break,
+                        Some('\\') => regex.push_str(r"\\"),
+                        Some(c) => regex.push(c),
+                        None => return Err(format!("Invalid glob {}, unclosed [", glob)),
+                    }
+                }
+                regex.push(']');
+            }
+            c => regex.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+
+    regex.push_str(GLOB_URL_SUFFIX);
+    Ok(regex)
+}
+
+/// Check if a glob is valid
+pub fn parse_glob(src: &str) -> Result<String, String> {
+    let regex = glob_to_regex(src)?;
+    Regex::new(&regex).map_err(|e| e.to_string())?;
+
+    Ok(src.to_string())
+}
+
+/// What a rule does to the URLs it matches
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Verdict {
+    Allow,
+    Deny,
+}
+
+/// Ordered allow and deny rules, the first rule matching an URL deciding its fate.
+/// Rule files have one rule per line, like `allow <regex>` or `deny glob:<glob>`, with `#`
+/// starting comments
+#[derive(Debug, Clone)]
+pub struct Rules {
+    verdicts: Vec<Verdict>,
+    patterns: RegexSet,
+}
+
+impl Rules {
+    /// Parse rules, `source` naming where they come from in the errors
+    pub fn parse(rules: &str, source: &str) -> Result<Rules, String> {
+        let mut verdicts = Vec::new();
+        let mut patterns = Vec::new();
+
+        for (number, line) in rules.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let error = |message: String| format!("{}:{}: {}", source, number + 1, message);
+            let (verdict, pattern) = match line.split_once(char::is_whitespace) {
+                Some(("allow", pattern)) => (Verdict::Allow, pattern.trim()),
+                Some(("deny", pattern)) => (Verdict::Deny, pattern.trim()),
+                _ => {
+                    return Err(error(format!(
+                        "Invalid rule {}, expected allow <pattern> or deny <pattern>",
+                        line
+                    )))
+                }
+            };
+            let pattern = match pattern.strip_prefix("glob:") {
+                Some(glob) => glob_to_regex(glob).map_err(error)?,
+                None => pattern.to_string(),
+            };
+            Regex::new(&pattern).map_err(|e| error(e.to_string()))?;
+
+            verdicts.push(verdict);
+            patterns.push(pattern);
+        }
+
+        Ok(Rules {
+            verdicts,
+            patterns: RegexSet::new(patterns).map_err(|e| e.to_string())?,
+        })
+    }
+
+    /// Read the rules of a file
+    pub fn from_file(path: &Path) -> Result<Rules, String> {
+        let rules = fs::read_to_string(path)
+            .map_err(|e| format!("Couldn't read {}: {}", path.display(), e))?;
+
+        Rules::parse(&rules, &path.display().to_string())
+    }
+
+    /// Return the verdict of the first rule matching an URL, if any
+    pub fn verdict(&self, url: &str) -> Option<Verdict> {
+        self.patterns
+            .matches(url)
+            .iter()
+            .next()
+            .map(|index| self.verdicts[index])
+    }
+}
+
+/// A filter deciding which URLs are visited or downloaded. The rules are checked first, in
+/// order. The URLs matching none of them must match one of the include patterns, if any, and
+/// none of the exclude patterns
+#[derive(Debug, Clone)]
+pub struct Filter {
+    rules: Vec<Rules>,
+    include: RegexSet,
+    exclude: RegexSet,
+}
+
+impl Filter {
+    /// Create a filter from include and exclude regexes and globs, and rules
+    pub fn new(
+        include: &[Regex],
+        exclude: &[Regex],
+        include_globs: &[String],
+        exclude_globs: &[String],
+        rules: Vec<Rules>,
+    ) -> Result<Filter, String> {
+        let set = |regexes: &[Regex], globs: &[String]| -> Result<RegexSet, String> {
+            let mut patterns: Vec<String> = regexes
+                .iter()
+                .map(|regex| regex.as_str().to_string())
+                .collect();
+            for glob in globs {
+                patterns.push(glob_to_regex(glob)?);
+            }
+
+            RegexSet::new(patterns).map_err(|e| e.to_string())
+        };
+
+        Ok(Filter {
+            rules,
+            include: set(include, include_globs)?,
+            exclude: set(exclude, exclude_globs)?,
+        })
+    }
+
+    /// Check if an URL passes the filter
+    pub fn is_match(&self, url: &Url) -> bool {
+        let url = url.as_str();
+
+        match self.rules.iter().find_map(|rules| rules.verdict(url)) {
+            Some(verdict) => verdict == Verdict::Allow,
+            None => {
+                (self.include.is_empty() || self.include.is_match(url))
+                    && !self.exclude.is_match(url)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob_matches(glob: &str, url: &str) -> bool {
+        Regex::new(&glob_to_regex(glob).unwrap())
+            .unwrap()
+            .is_match(url)
+    }
+
+    #[test]
+    fn globs() {
+        assert!(glob_matches("*.pdf", "https://example.com/docs/report.pdf"));
+        assert!(glob_matches(
+            "*.pdf",
+            "https://example.com/report.pdf?download=1"
+        ));
+        assert!(!glob_matches(
+            "*.pdf",
+            "https://example.com/report.pdf.html"
+        ));
+        assert!(!glob_matches("*.pdf", "https://example.pdf/"));
+        assert!(glob_matches(
+            "report-??.[pP][dD][fF]",
+            "https://a.com/report-01.PDF"
+        ));
+        assert!(!glob_matches("[!r]*", "https://a.com/report"));
+
+        assert!(glob_matches("/docs/*", "https://example.com/docs/a.html"));
+        assert!(!glob_matches(
+            "/docs/*",
+            "https://example.com/docs/v2/a.html"
+        ));
+        assert!(glob_matches(
+            "/docs/**",
+            "https://example.com/docs/v2/a.html"
+        ));
+        assert!(!glob_matches(
+            "/docs/*",
+            "https://example.com/blog/docs/a.html"
+        ));
+        assert!(glob_matches(
+            "docs/*",
+            "https://example.com/blog/docs/a.html"
+        ));
+
+        assert!(parse_glob("[abc").is_err());
+    }
+
+    #[test]
+    fn filter() {
+        let filter = Filter::new(
+            &[Regex::new("example").unwrap(), Regex::new("docs").unwrap()],
+            &[Regex::new("private").unwrap()],
+            &[],
+            &["*.zip".to_string()],
+            Vec::new(),
+        )
+        .unwrap();
+        let is_match = |url| filter.is_match(&Url::parse(url).unwrap());
+
+        assert!(is_match("https://example.com/"));
+        assert!(is_match("https://a.com/docs/"));
+        assert!(!is_match("https://a.com/"));
+        assert!(!is_match("https://example.com/private/"));
+        assert!(!is_match("https://example.com/archive.zip"));
+
+        let everything = Filter::new(&[], &[], &[], &[], Vec::new()).unwrap();
+        assert!(everything.is_match(&Url::parse("https://a.com/").unwrap()));
+    }
+
+    #[test]
+    fn rules() {
+        let rules = Rules::parse(
+            "# Only the public documentation\n\
+             deny /docs/private/\n\
+             \n\
+             allow glob:/docs/**\n\
+             deny .*\n",
+            "rules.txt",
+        )
+        .unwrap();
+        let filter = Filter::new(&[], &[], &[], &[], vec![rules]).unwrap();
+        let is_match = |url| filter.is_match(&Url::parse(url).unwrap());
+
+        assert!(is_match("https://example.com/docs/v2/intro.html"));
+        assert!(!is_match("https://example.com/docs/private/keys.html"));
+        assert!(!is_match("https://example.com/blog/"));
+
+        assert_eq!(
+            Rules::parse("allow a\nkeep b", "rules.txt").unwrap_err(),
+            "rules.txt:2: Invalid rule keep b, expected allow <pattern> or deny <pattern>"
+        );
+        assert!(Rules::parse("deny (", "rules.txt").is_err());
+    }
+}
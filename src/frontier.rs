@@ -0,0 +1,670 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap, VecDeque};
+use std::path::PathBuf;
+use std::str::FromStr;
+use std::sync::Mutex;
+
+use regex::Regex;
+use sled::Tree;
+use url::Url;
+
+use crate::error;
+
+use super::state;
+
+/// In which order the queued URLs are downloaded
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Strategy {
+    /// Breadth-first: the URLs closest to the origin first
+    Bfs,
+    /// Depth-first: the URLs found last first
+    Dfs,
+    /// The requisites of the pages first, then breadth-first
+    RequisitesFirst,
+    /// The shortest URLs first, usually the most important pages of a site
+    ShortestUrl,
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Strategy, String> {
+        match src {
+            "bfs" => Ok(Strategy::Bfs),
+            "dfs" => Ok(Strategy::Dfs),
+            "requisites-first" => Ok(Strategy::RequisitesFirst),
+            "shortest-url" => Ok(Strategy::ShortestUrl),
+            _ => Err(format!(
+                "Invalid strategy {}, expected bfs, dfs, requisites-first or shortest-url",
+                src
+            )),
+        }
+    }
+}
+
+/// A rule giving a priority to the URLs matching a regex, like `/docs/=10`. URLs with a
+/// higher priority are downloaded first, whatever the strategy
+#[derive(Debug, Clone)]
+pub struct PriorityRule {
+    pattern: Regex,
+    priority: i32,
+}
+
+impl FromStr for PriorityRule {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<PriorityRule, String> {
+        // The regex can contain '=' itself, not the priority
+        let (pattern, priority) = src
+            .rsplit_once('=')
+            .ok_or_else(|| format!("Invalid priority rule {}, expected <regex>=<priority>", src))?;
+        let priority = priority
+            .trim()
+            .parse::<i32>()
+            .map_err(|_| format!("Invalid priority {} in rule {}", priority, src))?;
+        let pattern = Regex::new(pattern).map_err(|e| e.to_string())?;
+
+        Ok(PriorityRule { pattern, priority })
+    }
+}
+
+/// An URL waiting to be downloaded, with the depths at which it was found
+#[derive(Debug, Clone, PartialEq)]
+pub struct Job {
+    pub url: Url,
+    pub depth: i32,
+    pub ext_depth: i32,
+}
+
+/// A queued job with its rank among the jobs of its host: its priority, its strategy key and
+/// its order, the highest ranked job being the next one
+struct Entry {
+    rank: (i32, Reverse<(i64, i64)>, i64),
+    job: Job,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Entry) -> bool {
+        self.rank == other.rank
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Entry) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Entry) -> Ordering {
+        self.rank.cmp(&other.rank)
+    }
+}
+
+/// Encode a number into bytes sorted like the numbers
+fn encode(number: i64) -> [u8; 8] {
+    ((number as u64) ^ (1 << 63)).to_be_bytes()
+}
+
+/// Decode a number encoded by `encode`
+fn decode(bytes: &[u8]) -> i64 {
+    let mut encoded = [0; 8];
+    encoded.copy_from_slice(&bytes[..8]);
+
+    (u64::from_be_bytes(encoded) ^ (1 << 63)) as i64
+}
+
+/// The queues of the hosts, in memory or in a database for very large crawls
+enum HostQueues {
+    Memory(HashMap<String, BinaryHeap<Entry>>),
+    /// The jobs keyed by their host and their rank, sorted in the order they are handed out,
+    /// like `<host>\0<-priority><key><-order>`
+    Disk(Tree),
+}
+
+/// Fail on a database error, which leaves the crawl without its queue
+fn check<T>(result: sled::Result<T>) -> T {
+    result.unwrap_or_else(|e| error!("Couldn't use the database: {}", e))
+}
+
+impl HostQueues {
+    /// Return the prefix of the database keys of the jobs of a host
+    fn prefix(host: &str) -> Vec<u8> {
+        let mut prefix = host.as_bytes().to_vec();
+        prefix.push(0);
+        prefix
+    }
+
+    /// Store a job of a host in the database
+    fn store(tree: &Tree, host: &str, entry: Entry) {
+        let (priority, Reverse((first, second)), order) = entry.rank;
+        let mut key = HostQueues::prefix(host);
+        for number in [-i64::from(priority), first, second, -order] {
+            key.extend_from_slice(&encode(number));
+        }
+        let job = format!(
+            "{} {} {}",
+            entry.job.depth, entry.job.ext_depth, entry.job.url
+        );
+
+        check(tree.insert(key, job.as_bytes()));
+    }
+
+    fn push(&mut self, host: String, entry: Entry) {
+        match self {
+            HostQueues::Memory(hosts) => hosts.entry(host).or_default().push(entry),
+            HostQueues::Disk(tree) => HostQueues::store(tree, &host, entry),
+        }
+    }
+
+    /// Move the jobs kept in memory to a database tree, keeping their order
+    fn spill(&mut self, tree: Tree) {
+        if let HostQueues::Memory(hosts) = self {
+            for (host, entries) in hosts.drain() {
+                for entry in entries {
+                    HostQueues::store(&tree, &host, entry);
+                }
+            }
+        }
+
+        *self = HostQueues::Disk(tree);
+    }
+
+    /// Return the priority of the next job of a host
+    fn head(&self, host: &str) -> Option<i32> {
+        match self {
+            HostQueues::Memory(hosts) => hosts.get(host)?.peek().map(|entry| entry.rank.0),
+            HostQueues::Disk(tree) => {
+                let prefix = HostQueues::prefix(host);
+                let (key, _) = check(tree.scan_prefix(&prefix).next().transpose())?;
+
+                Some(-decode(&key[prefix.len()..]) as i32)
+            }
+        }
+    }
+
+    /// Parse a job stored in the database, like `<depth> <ext_depth> <url>`
+    fn parse_job(value: &str) -> Option<Job> {
+        let mut parts = value.splitn(3, ' ');
+
+        Some(Job {
+            depth: parts.next()?.parse().ok()?,
+            ext_depth: parts.next()?.parse().ok()?,
+            url: Url::parse(parts.next()?).ok()?,
+        })
+    }
+
+    /// Take the next job of a host
+    fn pop(&mut self, host: &str) -> Option<Job> {
+        match self {
+            HostQueues::Memory(hosts) => {
+                let entry = hosts.get_mut(host)?.pop()?;
+                if hosts[host].is_empty() {
+                    hosts.remove(host);
+                }
+                Some(entry.job)
+            }
+            HostQueues::Disk(tree) => {
+                let prefix = HostQueues::prefix(host);
+                let (key, value) = check(tree.scan_prefix(&prefix).next().transpose())?;
+
+                let value = String::from_utf8_lossy(&value).to_string();
+                let job = HostQueues::parse_job(&value)
+                    .unwrap_or_else(|| error!("Invalid job in the database: {}", value));
+                check(tree.remove(key));
+
+                Some(job)
+            }
+        }
+    }
+}
+
+/// The queued jobs of each host, with the number of jobs ever queued to keep their order
+struct Queue {
+    hosts: HostQueues,
+    /// The number of queued jobs
+    len: usize,
+    /// The hosts with queued jobs, in the round-robin order
+    turns: VecDeque<String>,
+    /// The priority of the next job of each host in the turns, so they are compared without
+    /// reading the database
+    heads: HashMap<String, i32>,
+    /// The number of jobs of each host being downloaded
+    active: HashMap<String, usize>,
+    sequence: i64,
+}
+
+/// The queue of the URLs to download. Each host has its own queue, ordered by the priority
+/// rules and then by the strategy. The hosts whose next jobs have the highest priority take
+/// turns, so none of them starves the others: the strategy is only followed within each host,
+/// a breadth-first crawl of several hosts not being strictly breadth-first across them. The
+/// queues can be kept in a database tree, only the hosts being kept in memory, from the start
+/// or once the memory holds too many jobs
+pub struct Frontier {
+    strategy: Strategy,
+    rules: Vec<PriorityRule>,
+    max_jobs_per_host: Option<usize>,
+    /// The maximum number of jobs kept in memory, and the directory of the database taking
+    /// them beyond it
+    memory_limit: Option<(usize, PathBuf)>,
+    queue: Mutex<Queue>,
+}
+
+impl Frontier {
+    /// Create an empty frontier, handing out at most `max_jobs_per_host` jobs of a host at once
+    /// and keeping the jobs in `tree` if given
+    pub fn new(
+        strategy: Strategy,
+        rules: Vec<PriorityRule>,
+        max_jobs_per_host: Option<usize>,
+        tree: Option<Tree>,
+    ) -> Frontier {
+        Frontier {
+            strategy,
+            rules,
+            max_jobs_per_host,
+            memory_limit: None,
+            queue: Mutex::new(Queue {
+                hosts: tree.map_or_else(|| HostQueues::Memory(HashMap::new()), HostQueues::Disk),
+                len: 0,
+                turns: VecDeque::new(),
+                heads: HashMap::new(),
+                active: HashMap::new(),
+                sequence: 0,
+            }),
+        }
+    }
+
+    /// Move the jobs to a database in `dir` once more than `max_jobs` are kept in memory
+    pub fn with_memory_limit(mut self, max_jobs: usize, dir: PathBuf) -> Frontier {
+        self.memory_limit = Some((max_jobs, dir));
+        self
+    }
+
+    /// Return the key of the queue of the host of an URL
+    fn host_key(url: &Url) -> String {
+        url.host_str().unwrap_or_default().to_string()
+    }
+
+    /// Return the priority of an URL, given by the first matching rule
+    fn priority(&self, url: &Url) -> i32 {
+        self.rules
+            .iter()
+            .find(|rule| rule.pattern.is_match(url.as_str()))
+            .map_or(0, |rule| rule.priority)
+    }
+
+    /// Queue a job, `requisite` telling if it is needed to display a page. It never blocks, as
+    /// the workers taking the jobs are the ones queueing the links they find: the jobs beyond
+    /// the memory limit go to the database instead
+    pub fn push(&self, job: Job, requisite: bool) {
+        let depth = i64::from(job.depth) + i64::from(job.ext_depth);
+        let key = match self.strategy {
+            Strategy::Bfs => (depth, 0),
+            Strategy::Dfs => (0, 0),
+            Strategy::RequisitesFirst => (i64::from(!requisite), depth),
+            Strategy::ShortestUrl => (job.url.as_str().len() as i64, depth),
+        };
+        let priority = self.priority(&job.url);
+        let host = Frontier::host_key(&job.url);
+
+        let mut queue = self.queue.lock().unwrap();
+        queue.sequence += 1;
+        // Among equal jobs, the last one found comes first in depth-first order, the first one
+        // otherwise
+        let order = match self.strategy {
+            Strategy::Dfs => queue.sequence,
+            _ => -queue.sequence,
+        };
+
+        match queue.heads.get_mut(&host) {
+            Some(head) => *head = (*head).max(priority),
+            None => {
+                queue.heads.insert(host.clone(), priority);
+                queue.turns.push_back(host.clone());
+            }
+        }
+        queue.hosts.push(
+            host,
+            Entry {
+                rank: (priority, Reverse(key), order),
+                job,
+            },
+        );
+        queue.len += 1;
+
+        if let (HostQueues::Memory(_), Some((max_jobs, dir))) = (&queue.hosts, &self.memory_limit) {
+            if queue.len > *max_jobs {
+                let database = state::open_database(dir).unwrap_or_else(|e| error!("{}", e));
+                queue.hosts.spill(state::open_tree(&database, "frontier"));
+            }
+        }
+    }
+
+    /// Take the next job to download, if any. The job is handed out by the next host in turn
+    /// among the ones whose next job has the highest priority and having less than the maximum
+    /// number of jobs being downloaded. `finish` must be called once it is done
+    pub fn pop(&self) -> Option<Job> {
+        let mut guard = self.queue.lock().unwrap();
+        let queue = &mut *guard;
+
+        let available = |queue: &Queue, host: &String| match self.max_jobs_per_host {
+            Some(max) => queue.active.get(host).copied().unwrap_or(0) < max,
+            None => true,
+        };
+        let head = |queue: &Queue, host: &String| queue.heads.get(host).copied();
+
+        let best = queue
+            .turns
+            .iter()
+            .filter(|host| available(queue, host))
+            .filter_map(|host| head(queue, host))
+            .max()?;
+        let turn = queue
+            .turns
+            .iter()
+            .position(|host| available(queue, host) && head(queue, host) == Some(best))?;
+
+        // The host goes to the end of the turns, or leaves them if it has no job left
+        let host = queue.turns.remove(turn)?;
+        let job = queue.hosts.pop(&host);
+        match queue.hosts.head(&host) {
+            Some(next) => {
+                queue.heads.insert(host.clone(), next);
+                queue.turns.push_back(host.clone());
+            }
+            None => {
+                queue.heads.remove(&host);
+            }
+        }
+        if job.is_some() {
+            queue.len -= 1;
+            *queue.active.entry(host).or_insert(0) += 1;
+        }
+
+        job
+    }
+
+    /// Tell that the download of a job given by `pop` is done
+    pub fn finish(&self, job: &Job) {
+        let mut queue = self.queue.lock().unwrap();
+        if let Some(active) = queue.active.get_mut(&Frontier::host_key(&job.url)) {
+            *active = active.saturating_sub(1);
+        }
+    }
+
+    /// Check if no job is queued, which doesn't mean that no job is being downloaded
+    pub fn is_empty(&self) -> bool {
+        self.queue.lock().unwrap().turns.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state;
+
+    fn job(url: &str, depth: i32) -> Job {
+        Job {
+            url: Url::parse(url).unwrap(),
+            depth,
+            ext_depth: 0,
+        }
+    }
+
+    fn order(frontier: &Frontier) -> Vec<String> {
+        std::iter::from_fn(|| frontier.pop())
+            .map(|job| job.url.path().to_string())
+            .collect()
+    }
+
+    fn fill(frontier: &Frontier) {
+        frontier.push(job("https://a.com/blog/", 1), false);
+        frontier.push(job("https://a.com/blog/post-1/", 2), false);
+        frontier.push(job("https://a.com/a.css", 1), true);
+        frontier.push(job("https://a.com/docs/", 1), false);
+        frontier.push(job("https://a.com/blog/post-1/a.png", 2), true);
+    }
+
+    #[test]
+    fn strategies() {
+        let expected = [
+            (
+                Strategy::Bfs,
+                vec![
+                    "/blog/",
+                    "/a.css",
+                    "/docs/",
+                    "/blog/post-1/",
+                    "/blog/post-1/a.png",
+                ],
+            ),
+            (
+                Strategy::Dfs,
+                vec![
+                    "/blog/post-1/a.png",
+                    "/docs/",
+                    "/a.css",
+                    "/blog/post-1/",
+                    "/blog/",
+                ],
+            ),
+            (
+                Strategy::RequisitesFirst,
+                vec![
+                    "/a.css",
+                    "/blog/post-1/a.png",
+                    "/blog/",
+                    "/docs/",
+                    "/blog/post-1/",
+                ],
+            ),
+            (
+                Strategy::ShortestUrl,
+                vec![
+                    "/blog/",
+                    "/a.css",
+                    "/docs/",
+                    "/blog/post-1/",
+                    "/blog/post-1/a.png",
+                ],
+            ),
+        ];
+
+        for (strategy, expected) in expected.iter() {
+            let frontier = Frontier::new(*strategy, Vec::new(), None, None);
+            fill(&frontier);
+            assert_eq!(order(&frontier), *expected, "{:?}", strategy);
+        }
+    }
+
+    #[test]
+    fn priorities() {
+        let rules = vec!["/docs/=10".parse().unwrap(), "/blog/=-1".parse().unwrap()];
+        let frontier = Frontier::new(Strategy::Bfs, rules, None, None);
+        fill(&frontier);
+
+        assert_eq!(
+            order(&frontier),
+            vec![
+                "/docs/",
+                "/a.css",
+                "/blog/",
+                "/blog/post-1/",
+                "/blog/post-1/a.png",
+            ]
+        );
+
+        assert!("/docs/".parse::<PriorityRule>().is_err());
+        assert!("/docs/=high".parse::<PriorityRule>().is_err());
+    }
+
+    #[test]
+    fn round_robin() {
+        let frontier = Frontier::new(Strategy::Bfs, Vec::new(), None, None);
+        for page in 1..=3 {
+            frontier.push(job(&format!("https://cdn.com/{}.png", page), 1), true);
+        }
+        frontier.push(job("https://a.com/1.html", 1), false);
+        frontier.push(job("https://a.com/2.html", 1), false);
+
+        let hosts: Vec<String> = std::iter::from_fn(|| frontier.pop())
+            .map(|job| job.url.to_string())
+            .collect();
+        assert_eq!(
+            hosts,
+            vec![
+                "https://cdn.com/1.png",
+                "https://a.com/1.html",
+                "https://cdn.com/2.png",
+                "https://a.com/2.html",
+                "https://cdn.com/3.png",
+            ]
+        );
+    }
+
+    #[test]
+    fn strategy_across_hosts() {
+        let frontier = Frontier::new(Strategy::Bfs, Vec::new(), None, None);
+        frontier.push(job("https://a.com/deep/", 5), false);
+        frontier.push(job("https://b.com/", 1), false);
+        frontier.push(job("https://a.com/", 1), false);
+
+        let urls: Vec<String> = std::iter::from_fn(|| frontier.pop())
+            .map(|job| job.url.to_string())
+            .collect();
+        assert_eq!(
+            urls,
+            vec!["https://a.com/", "https://b.com/", "https://a.com/deep/"]
+        );
+    }
+
+    #[test]
+    fn shortest_url_across_hosts() {
+        let frontier = Frontier::new(Strategy::ShortestUrl, Vec::new(), None, None);
+        frontier.push(job("https://a.com/1", 1), false);
+        frontier.push(job("https://a.com/2", 1), false);
+        frontier.push(job("https://a.com/3", 1), false);
+        frontier.push(job("https://b.com/a/long/path", 1), false);
+        frontier.push(job("https://b.com/a/longer/path", 1), false);
+
+        // The shorter URLs of a.com don't starve b.com
+        let urls: Vec<String> = std::iter::from_fn(|| frontier.pop())
+            .map(|job| job.url.to_string())
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://a.com/1",
+                "https://b.com/a/long/path",
+                "https://a.com/2",
+                "https://b.com/a/longer/path",
+                "https://a.com/3",
+            ]
+        );
+    }
+
+    #[test]
+    fn priority_across_hosts() {
+        let rules = vec!["/docs/=10".parse().unwrap()];
+        let frontier = Frontier::new(Strategy::Bfs, rules, None, None);
+        frontier.push(job("https://a.com/", 1), false);
+        frontier.push(job("https://b.com/docs/1", 3), false);
+        frontier.push(job("https://b.com/docs/2", 3), false);
+
+        // The hosts only take turns among the ones with the highest priority
+        let urls: Vec<String> = std::iter::from_fn(|| frontier.pop())
+            .map(|job| job.url.to_string())
+            .collect();
+        assert_eq!(
+            urls,
+            vec![
+                "https://b.com/docs/1",
+                "https://b.com/docs/2",
+                "https://a.com/",
+            ]
+        );
+    }
+
+    #[test]
+    fn max_jobs_per_host() {
+        let frontier = Frontier::new(Strategy::Bfs, Vec::new(), Some(1), None);
+        frontier.push(job("https://cdn.com/1.png", 1), true);
+        frontier.push(job("https://cdn.com/2.png", 1), true);
+        frontier.push(job("https://a.com/", 1), false);
+
+        let first = frontier.pop().unwrap();
+        assert_eq!(first.url.as_str(), "https://cdn.com/1.png");
+        assert_eq!(frontier.pop().unwrap().url.as_str(), "https://a.com/");
+        // The next job of cdn.com waits for the first one
+        assert_eq!(frontier.pop(), None);
+        assert!(!frontier.is_empty());
+
+        frontier.finish(&first);
+        assert_eq!(
+            frontier.pop().unwrap().url.as_str(),
+            "https://cdn.com/2.png"
+        );
+        assert!(frontier.is_empty());
+    }
+
+    #[test]
+    fn memory_limit() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+
+        for strategy in [Strategy::Bfs, Strategy::Dfs, Strategy::ShortestUrl].iter() {
+            let memory = Frontier::new(*strategy, Vec::new(), None, None);
+            let spilled = Frontier::new(*strategy, Vec::new(), None, None)
+                .with_memory_limit(3, dir.to_path_buf());
+            for frontier in [&memory, &spilled] {
+                fill(frontier);
+                frontier.push(job("https://cdn.com/a.png", 2), true);
+            }
+
+            let queue = spilled.queue.lock().unwrap();
+            assert!(matches!(queue.hosts, HostQueues::Disk(_)));
+            drop(queue);
+            assert_eq!(order(&spilled), order(&memory), "{:?}", strategy);
+            assert!(spilled.is_empty());
+        }
+    }
+
+    #[test]
+    fn stored_jobs() {
+        assert_eq!(
+            HostQueues::parse_job("1 2 https://a.com/a b"),
+            Some(Job {
+                url: Url::parse("https://a.com/a%20b").unwrap(),
+                depth: 1,
+                ext_depth: 2,
+            })
+        );
+        assert_eq!(HostQueues::parse_job("1 https://a.com/"), None);
+        assert_eq!(HostQueues::parse_job("1 2 /a"), None);
+    }
+
+    #[test]
+    fn database() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+        let database = state::open_database(&dir).unwrap();
+        let rules = vec!["/docs/=10".parse().unwrap()];
+
+        for (number, strategy) in [Strategy::Bfs, Strategy::Dfs, Strategy::ShortestUrl]
+            .iter()
+            .enumerate()
+        {
+            let tree = state::open_tree(&database, &number.to_string());
+            let memory = Frontier::new(*strategy, rules.clone(), None, None);
+            let disk = Frontier::new(*strategy, rules.clone(), None, Some(tree));
+            for frontier in [&memory, &disk] {
+                fill(frontier);
+                frontier.push(job("https://cdn.com/a.png", 2), true);
+            }
+
+            assert_eq!(order(&disk), order(&memory), "{:?}", strategy);
+            assert!(disk.is_empty());
+        }
+    }
+}
@@ -1,8 +1,29 @@
 pub mod args;
+pub mod bloom;
+pub mod budget;
+pub mod cache;
+pub mod canonical;
+pub mod content_disposition;
+pub mod content_filter;
+pub mod css;
+pub mod depth;
 pub mod disk;
 pub mod dom;
 pub mod downloader;
+pub mod filter;
+pub mod frontier;
 pub mod logger;
+pub mod mime_sniff;
+pub mod network_policy;
+pub mod rate_limit;
+pub mod resolver;
 pub mod response;
+pub mod rewrite;
+pub mod scope;
 pub mod scraper;
+pub mod simhash;
+pub mod soft404;
+pub mod state;
+pub mod summary;
+pub mod trap;
 pub mod url_helper;
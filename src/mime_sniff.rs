@@ -0,0 +1,377 @@
+/// Number of bytes of the resource inspected when sniffing
+const RESOURCE_HEADER_LENGTH: usize = 1445;
+
+/// A byte pattern, its mask, the bytes ignored before it and the resulting MIME type
+struct Pattern {
+    pattern: &'static [u8],
+    mask: &'static [u8],
+    ignored: &'static [u8],
+    mime: &'static str,
+}
+
+/// Whitespace bytes as defined by the standard
+const WHITESPACE: &[u8] = b"\x09\x0a\x0c\x0d\x20";
+
+/// Tags identifying an HTML document, they must be followed by a space or `>`
+const HTML_TAGS: [&[u8]; 17] = [
+    b"<!DOCTYPE HTML",
+    b"<HTML",
+    b"<HEAD",
+    b"<SCRIPT",
+    b"<IFRAME",
+    b"<H1",
+    b"<DIV",
+    b"<FONT",
+    b"<TABLE",
+    b"<A",
+    b"<STYLE",
+    b"<TITLE",
+    b"<B",
+    b"<BODY",
+    b"<BR",
+    b"<P",
+    b"<!--",
+];
+
+/// Patterns which are not HTML, in the order given by the standard
+const PATTERNS: [Pattern; 29] = [
+    Pattern {
+        pattern: b"<?xml",
+        mask: b"\xff\xff\xff\xff\xff",
+        ignored: WHITESPACE,
+        mime: "text/xml",
+    },
+    Pattern {
+        pattern: b"%PDF-",
+        mask: b"\xff\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "application/pdf",
+    },
+    Pattern {
+        pattern: b"%!PS-Adobe-",
+        mask: b"\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "application/postscript",
+    },
+    Pattern {
+        pattern: b"\xfe\xff\x00\x00",
+        mask: b"\xff\xff\x00\x00",
+        ignored: b"",
+        mime: "text/plain",
+    },
+    Pattern {
+        pattern: b"\xff\xfe\x00\x00",
+        mask: b"\xff\xff\x00\x00",
+        ignored: b"",
+        mime: "text/plain",
+    },
+    Pattern {
+        pattern: b"\xef\xbb\xbf\x00",
+        mask: b"\xff\xff\xff\x00",
+        ignored: b"",
+        mime: "text/plain",
+    },
+    // Images
+    Pattern {
+        pattern: b"\x00\x00\x01\x00",
+        mask: b"\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "image/x-icon",
+    },
+    Pattern {
+        pattern: b"\x00\x00\x02\x00",
+        mask: b"\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "image/x-icon",
+    },
+    Pattern {
+        pattern: b"BM",
+        mask: b"\xff\xff",
+        ignored: b"",
+        mime: "image/bmp",
+    },
+    Pattern {
+        pattern: b"GIF87a",
+        mask: b"\xff\xff\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "image/gif",
+    },
+    Pattern {
+        pattern: b"GIF89a",
+        mask: b"\xff\xff\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "image/gif",
+    },
+    Pattern {
+        pattern: b"RIFF\x00\x00\x00\x00WEBPVP",
+        mask: b"\xff\xff\xff\xff\x00\x00\x00\x00\xff\xff\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "image/webp",
+    },
+    Pattern {
+        pattern: b"\x89PNG\x0d\x0a\x1a\x0a",
+        mask: b"\xff\xff\xff\xff\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "image/png",
+    },
+    Pattern {
+        pattern: b"\xff\xd8\xff",
+        mask: b"\xff\xff\xff",
+        ignored: b"",
+        mime: "image/jpeg",
+    },
+    // Audio and video
+    Pattern {
+        pattern: b"\x1a\x45\xdf\xa3",
+        mask: b"\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "video/webm",
+    },
+    Pattern {
+        pattern: b".snd",
+        mask: b"\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "audio/basic",
+    },
+    Pattern {
+        pattern: b"FORM\x00\x00\x00\x00AIFF",
+        mask: b"\xff\xff\xff\xff\x00\x00\x00\x00\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "audio/aiff",
+    },
+    Pattern {
+        pattern: b"ID3",
+        mask: b"\xff\xff\xff",
+        ignored: b"",
+        mime: "audio/mpeg",
+    },
+    Pattern {
+        pattern: b"OggS\x00",
+        mask: b"\xff\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "application/ogg",
+    },
+    Pattern {
+        pattern: b"MThd\x00\x00\x00\x06",
+        mask: b"\xff\xff\xff\xff\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "audio/midi",
+    },
+    Pattern {
+        pattern: b"RIFF\x00\x00\x00\x00AVI ",
+        mask: b"\xff\xff\xff\xff\x00\x00\x00\x00\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "video/avi",
+    },
+    Pattern {
+        pattern: b"RIFF\x00\x00\x00\x00WAVE",
+        mask: b"\xff\xff\xff\xff\x00\x00\x00\x00\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "audio/wave",
+    },
+    // Fonts
+    Pattern {
+        pattern: b"\x00\x01\x00\x00",
+        mask: b"\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "font/ttf",
+    },
+    Pattern {
+        pattern: b"OTTO",
+        mask: b"\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "font/otf",
+    },
+    Pattern {
+        pattern: b"wOFF",
+        mask: b"\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "font/woff",
+    },
+    Pattern {
+        pattern: b"wOF2",
+        mask: b"\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "font/woff2",
+    },
+    // Archives
+    Pattern {
+        pattern: b"\x1f\x8b\x08",
+        mask: b"\xff\xff\xff",
+        ignored: b"",
+        mime: "application/x-gzip",
+    },
+    Pattern {
+        pattern: b"PK\x03\x04",
+        mask: b"\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "application/zip",
+    },
+    Pattern {
+        pattern: b"Rar \x1a\x07\x00",
+        mask: b"\xff\xff\xff\xff\xff\xff\xff",
+        ignored: b"",
+        mime: "application/x-rar-compressed",
+    },
+];
+
+impl Pattern {
+    /// The pattern matching algorithm of the standard
+    fn matches(&self, data: &[u8]) -> bool {
+        let start = data
+            .iter()
+            .position(|byte| !self.ignored.contains(byte))
+            .unwrap_or(data.len());
+        let data = &data[start..];
+
+        data.len() >= self.pattern.len()
+            && self
+                .pattern
+                .iter()
+                .zip(self.mask)
+                .zip(data)
+                .all(|((pattern, mask), byte)| byte & mask == *pattern)
+    }
+}
+
+/// Check if the data starts with an HTML tag, ignoring the leading whitespace
+fn is_html(data: &[u8]) -> bool {
+    let start = data
+        .iter()
+        .position(|byte| !WHITESPACE.contains(byte))
+        .unwrap_or(data.len());
+    let data = &data[start..];
+
+    HTML_TAGS.iter().any(|tag| {
+        data.len() > tag.len()
+            && data[..tag.len()].eq_ignore_ascii_case(tag)
+            && matches!(data[tag.len()], b' ' | b'>')
+    })
+}
+
+/// Check if the data is an MP4 file, using the `ftyp` box of the header
+fn is_mp4(data: &[u8]) -> bool {
+    if data.len() < 12 {
+        return false;
+    }
+
+    let box_size = u32::from_be_bytes([data[0], data[1], data[2], data[3]]) as usize;
+    if data.len() < box_size || box_size & 3 != 0 || &data[4..8] != b"ftyp" {
+        return false;
+    }
+
+    if &data[8..11] == b"mp4" {
+        return true;
+    }
+
+    (16..box_size)
+        .step_by(4)
+        .any(|index| data.len() >= index + 3 && &data[index..index + 3] == b"mp4")
+}
+
+/// Check if the data contains a byte which is never found in text
+fn contains_binary_bytes(data: &[u8]) -> bool {
+    data.iter()
+        .any(|byte| matches!(byte, 0x00..=0x08 | 0x0b | 0x0e..=0x1a | 0x1c..=0x1f))
+}
+
+/// Check if a MIME type sent by the server is too vague to be trusted, and should be sniffed
+pub fn is_unknown(mime: &str) -> bool {
+    matches!(
+        mime,
+        "application/octet-stream" | "application/unknown" | "unknown/unknown" | "*/*"
+    )
+}
+
+/// Determine the MIME type of a resource whose type is unknown, using its first bytes.
+/// This follows the WHATWG MIME Sniffing Standard: https://mimesniff.spec.whatwg.org/
+pub fn sniff(data: &[u8]) -> &'static str {
+    let data = &data[..data.len().min(RESOURCE_HEADER_LENGTH)];
+
+    if is_html(data) {
+        return "text/html";
+    }
+
+    if let Some(pattern) = PATTERNS.iter().find(|pattern| pattern.matches(data)) {
+        return pattern.mime;
+    }
+
+    if is_mp4(data) {
+        return "video/mp4";
+    }
+
+    if contains_binary_bytes(data) {
+        "application/octet-stream"
+    } else {
+        "text/plain"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sniff_html() {
+        assert_eq!(sniff(b"<!DOCTYPE html>\n<html></html>"), "text/html");
+        assert_eq!(sniff(b"\n  \t<HTML lang=\"en\">"), "text/html");
+        assert_eq!(sniff(b"<!-- comment -->"), "text/html");
+        assert_eq!(sniff(b"<p>paragraph</p>"), "text/html");
+        assert_eq!(sniff(b"<pre>not a tag we know"), "text/plain");
+        assert_eq!(sniff(b"<?xml version=\"1.0\"?>"), "text/xml");
+    }
+
+    #[test]
+    fn sniff_images() {
+        assert_eq!(
+            sniff(b"\x89PNG\x0d\x0a\x1a\x0a\x00\x00\x00\x0dIHDR"),
+            "image/png"
+        );
+        assert_eq!(sniff(b"\xff\xd8\xff\xdb\x00\x43\x00"), "image/jpeg");
+        assert_eq!(sniff(b"GIF89a\x01\x00\x01\x00"), "image/gif");
+        assert_eq!(sniff(b"RIFF\x24\x00\x00\x00WEBPVP8 "), "image/webp");
+        assert_eq!(sniff(b"\x00\x00\x01\x00\x01\x00"), "image/x-icon");
+    }
+
+    #[test]
+    fn sniff_documents_and_archives() {
+        assert_eq!(sniff(b"%PDF-1.7\n%\xe2\xe3\xcf\xd3"), "application/pdf");
+        assert_eq!(sniff(b"PK\x03\x04\x14\x00\x00\x00"), "application/zip");
+        assert_eq!(
+            sniff(b"\x1f\x8b\x08\x00\x00\x00\x00\x00"),
+            "application/x-gzip"
+        );
+        assert_eq!(
+            sniff(b"Rar \x1a\x07\x00\xcf"),
+            "application/x-rar-compressed"
+        );
+    }
+
+    #[test]
+    fn sniff_media() {
+        assert_eq!(sniff(b"ID3\x04\x00\x00\x00\x00"), "audio/mpeg");
+        assert_eq!(sniff(b"OggS\x00\x02\x00\x00"), "application/ogg");
+        assert_eq!(
+            sniff(b"\x00\x00\x00\x18ftypmp42\x00\x00\x00\x00mp42isom"),
+            "video/mp4"
+        );
+        assert_eq!(sniff(b"wOF2\x00\x01\x00\x00"), "font/woff2");
+    }
+
+    #[test]
+    fn sniff_text_and_binary() {
+        assert_eq!(sniff(b"Hello"), "text/plain");
+        assert_eq!(sniff(b""), "text/plain");
+        assert_eq!(
+            sniff(b"\x00\x01\x02\x03garbage"),
+            "application/octet-stream"
+        );
+    }
+
+    #[test]
+    fn unknown_types() {
+        assert!(is_unknown("application/octet-stream"));
+        assert!(!is_unknown("application/pdf"));
+        assert!(!is_unknown("text/html"));
+    }
+}
@@ -0,0 +1,200 @@
+use std::error::Error;
+use std::fmt;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use ipnet::IpNet;
+use lazy_static::lazy_static;
+use url::{Host, Url};
+
+lazy_static! {
+    /// Loopback, link-local, private, multicast and other non-public ranges
+    static ref RESTRICTED_NETWORKS: Vec<IpNet> = [
+        "0.0.0.0/8",
+        "10.0.0.0/8",
+        "100.64.0.0/10",
+        "127.0.0.0/8",
+        "169.254.0.0/16",
+        "172.16.0.0/12",
+        "192.0.0.0/24",
+        "192.168.0.0/16",
+        "198.18.0.0/15",
+        "224.0.0.0/4",
+        "240.0.0.0/4",
+        "::/128",
+        "::1/128",
+        "fc00::/7",
+        "fe80::/10",
+        "fec0::/10",
+        "ff00::/8",
+    ]
+    .iter()
+    .map(|network| network.parse().unwrap())
+    .collect();
+}
+
+/// Error returned when a request would reach a restricted address
+#[derive(Debug, Clone, PartialEq)]
+pub struct RefusedAddress {
+    pub host: String,
+    pub addr: IpAddr,
+}
+
+impl fmt::Display for RefusedAddress {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} resolves to the restricted address {}",
+            self.host, self.addr
+        )
+    }
+}
+
+impl Error for RefusedAddress {}
+
+/// Decides which addresses the downloader may connect to. When enabled, loopback,
+/// link-local, private and multicast addresses are refused, unless they are in the allowlist
+#[derive(Debug, Clone)]
+pub struct NetworkPolicy {
+    allowed: Vec<IpNet>,
+}
+
+impl NetworkPolicy {
+    /// Create a policy refusing restricted addresses which are not in `allowed`
+    pub fn new(allowed: &[IpNet]) -> NetworkPolicy {
+        NetworkPolicy {
+            allowed: allowed.to_vec(),
+        }
+    }
+
+    /// Return the IPv4 address embedded in an IPv4-mapped, IPv4-compatible, NAT64, 6to4 or
+    /// Teredo IPv6 address, which reaches it through a gateway
+    fn embedded_ipv4(addr: &Ipv6Addr) -> Option<Ipv4Addr> {
+        let ipv4 = |high: u16, low: u16| Ipv4Addr::from(((high as u32) << 16) | low as u32);
+
+        match addr.segments() {
+            [0, 0, 0, 0, 0, 0xffff, high, low]
+            | [0, 0, 0, 0, 0, 0, high, low]
+            | [0x64, 0xff9b, 0, 0, 0, 0, high, low]
+            | [0x2002, high, low, ..] => Some(ipv4(high, low)),
+            // The address of the Teredo client is inverted
+            [0x2001, 0, _, _, _, _, high, low] => Some(ipv4(!high, !low)),
+            _ => None,
+        }
+    }
+
+    /// Check if an address is not publicly routable
+    fn is_restricted(addr: &IpAddr) -> bool {
+        if let IpAddr::V6(addr) = addr {
+            if let Some(addr) = NetworkPolicy::embedded_ipv4(addr) {
+                return NetworkPolicy::is_restricted(&IpAddr::V4(addr));
+            }
+        }
+
+        RESTRICTED_NETWORKS
+            .iter()
+            .any(|network| network.contains(addr))
+    }
+
+    /// Check if the downloader may connect to this address
+    pub fn is_allowed(&self, addr: &IpAddr) -> bool {
+        self.allowed.iter().any(|network| network.contains(addr))
+            || !NetworkPolicy::is_restricted(addr)
+    }
+
+    /// Check the host of a URL if it is an IP address. Domain names are checked once
+    /// resolved, as they are not enough to know where the request will go
+    pub fn check_url(&self, url: &Url) -> Result<(), RefusedAddress> {
+        let addr = match url.host() {
+            Some(Host::Ipv4(addr)) => IpAddr::V4(addr),
+            Some(Host::Ipv6(addr)) => IpAddr::V6(addr),
+            _ => return Ok(()),
+        };
+
+        if self.is_allowed(&addr) {
+            Ok(())
+        } else {
+            Err(RefusedAddress {
+                host: url.host_str().unwrap().to_string(),
+                addr,
+            })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn allowed(policy: &NetworkPolicy, addr: &str) -> bool {
+        policy.is_allowed(&addr.parse().unwrap())
+    }
+
+    #[test]
+    fn restricted_addresses() {
+        let policy = NetworkPolicy::new(&[]);
+
+        for addr in [
+            "127.0.0.1",
+            "10.1.2.3",
+            "172.31.255.255",
+            "192.168.0.1",
+            "169.254.169.254",
+            "100.64.0.1",
+            "0.0.0.0",
+            "224.0.0.251",
+            "255.255.255.255",
+            "::1",
+            "::",
+            "fe80::1",
+            "fd00::1",
+            "ff02::1",
+            "::ffff:127.0.0.1",
+            "::127.0.0.1",
+            "::10.0.0.1",
+            "64:ff9b::a9fe:a9fe",
+            "2002:a9fe:a9fe::1",
+            "2002:c0a8:1::",
+            "2001:0:4136:e378:8000:63bf:80ff:fffe",
+        ] {
+            assert!(!allowed(&policy, addr), "{} should be refused", addr);
+        }
+    }
+
+    #[test]
+    fn public_addresses() {
+        let policy = NetworkPolicy::new(&[]);
+
+        for addr in [
+            "93.184.216.34",
+            "172.32.0.1",
+            "8.8.8.8",
+            "2606:2800:220:1:248:1893:25c8:1946",
+            "::ffff:8.8.8.8",
+            "::8.8.8.8",
+            "2002:808:808::1",
+            "2001:0:4136:e378:8000:63bf:f7f7:f7f7",
+        ] {
+            assert!(allowed(&policy, addr), "{} should be allowed", addr);
+        }
+    }
+
+    #[test]
+    fn allowlist() {
+        let policy = NetworkPolicy::new(&["10.0.0.0/24".parse().unwrap()]);
+
+        assert!(allowed(&policy, "10.0.0.42"));
+        assert!(!allowed(&policy, "10.0.1.42"));
+    }
+
+    #[test]
+    fn check_url() {
+        let policy = NetworkPolicy::new(&[]);
+        let check = |url| policy.check_url(&Url::parse(url).unwrap());
+
+        assert!(check("http://169.254.169.254/latest/meta-data/").is_err());
+        assert!(check("http://[::1]:8080/admin").is_err());
+        assert!(check("http://2130706433/").is_err());
+        assert!(check("https://example.com/").is_ok());
+        assert!(check("https://8.8.8.8/").is_ok());
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Tokens of a bucket and the last time they were refilled
+struct BucketState {
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+/// A token bucket holding at most one second of transfer. Readers take the tokens of the
+/// bytes they received, and go into debt when there are not enough of them. The debt is
+/// paid by waiting, so the threads sharing the bucket never exceed its rate together
+pub struct TokenBucket {
+    rate: f64,
+    state: Mutex<BucketState>,
+}
+
+impl TokenBucket {
+    /// Create a full bucket allowing `rate` bytes per second
+    pub fn new(rate: u64) -> TokenBucket {
+        TokenBucket {
+            rate: rate as f64,
+            state: Mutex::new(BucketState {
+                tokens: rate as f64,
+                refilled_at: Instant::now(),
+            }),
+        }
+    }
+
+    /// Take the tokens of `amount` bytes at `now`, returning how long to wait for them
+    fn reserve(&self, amount: usize, now: Instant) -> Duration {
+        let mut state = self.state.lock().unwrap();
+
+        let elapsed = now.saturating_duration_since(state.refilled_at);
+        state.tokens = (state.tokens + elapsed.as_secs_f64() * self.rate).min(self.rate);
+        state.refilled_at = now;
+        state.tokens -= amount as f64;
+
+        if state.tokens < 0.0 {
+            Duration::from_secs_f64(-state.tokens / self.rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// Bandwidth limits of the downloads, for all of them and for each host
+pub struct RateLimiter {
+    global: Option<TokenBucket>,
+    per_host_rate: Option<u64>,
+    hosts: Mutex<HashMap<String, Arc<TokenBucket>>>,
+}
+
+impl RateLimiter {
+    /// Create a limiter, without limits when the rates are `None`
+    pub fn new(global_rate: Option<u64>, per_host_rate: Option<u64>) -> RateLimiter {
+        RateLimiter {
+            global: global_rate.map(TokenBucket::new),
+            per_host_rate,
+            hosts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// The lowest limit, which is the most bytes worth reading at once
+    pub fn min_rate(&self) -> Option<u64> {
+        let global_rate = self.global.as_ref().map(|bucket| bucket.rate as u64);
+
+        match (global_rate, self.per_host_rate) {
+            (Some(global_rate), Some(per_host_rate)) => Some(global_rate.min(per_host_rate)),
+            (global_rate, per_host_rate) => global_rate.or(per_host_rate),
+        }
+    }
+
+    /// Account for `amount` bytes received from `host`, sleeping if they came too fast
+    pub fn throttle(&self, host: Option<&str>, amount: usize) {
+        let now = Instant::now();
+
+        let global_wait = self
+            .global
+            .as_ref()
+            .map_or(Duration::ZERO, |bucket| bucket.reserve(amount, now));
+
+        let host_wait = match (self.per_host_rate, host) {
+            (Some(rate), Some(host)) => {
+                let bucket = self
+                    .hosts
+                    .lock()
+                    .unwrap()
+                    .entry(host.to_string())
+                    .or_insert_with(|| Arc::new(TokenBucket::new(rate)))
+                    .clone();
+                bucket.reserve(amount, now)
+            }
+            _ => Duration::ZERO,
+        };
+
+        let wait = global_wait.max(host_wait);
+        if !wait.is_zero() {
+            thread::sleep(wait);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_burst_then_debt() {
+        let bucket = TokenBucket::new(1000);
+        let now = Instant::now();
+
+        assert_eq!(bucket.reserve(1000, now), Duration::ZERO);
+        assert_eq!(bucket.reserve(500, now), Duration::from_millis(500));
+        // The debt is shared by the next readers
+        assert_eq!(bucket.reserve(500, now), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn bucket_refills() {
+        let bucket = TokenBucket::new(1000);
+        let now = Instant::now();
+
+        assert_eq!(bucket.reserve(1000, now), Duration::ZERO);
+        assert_eq!(
+            bucket.reserve(250, now + Duration::from_millis(250)),
+            Duration::ZERO
+        );
+        // Idle time never gives more than one second of tokens
+        assert_eq!(
+            bucket.reserve(1500, now + Duration::from_secs(10)),
+            Duration::from_millis(500)
+        );
+    }
+
+    #[test]
+    fn min_rate() {
+        assert_eq!(RateLimiter::new(None, None).min_rate(), None);
+        assert_eq!(RateLimiter::new(Some(100), None).min_rate(), Some(100));
+        assert_eq!(RateLimiter::new(None, Some(50)).min_rate(), Some(50));
+        assert_eq!(RateLimiter::new(Some(100), Some(50)).min_rate(), Some(50));
+    }
+}
@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::net::{IpAddr, SocketAddr};
+use std::str::FromStr;
+
+use reqwest::dns::{Addrs, Name, Resolve, Resolving};
+
+use super::network_policy::{NetworkPolicy, RefusedAddress};
+
+/// A static resolution of a host, written `host:*:addr[,addr]...` like curl's `--resolve`.
+/// DNS has no notion of ports, so the override applies to every port of the host and only
+/// the `*` port is accepted
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostOverride {
+    pub host: String,
+    pub addrs: Vec<IpAddr>,
+}
+
+impl FromStr for HostOverride {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<HostOverride, String> {
+        let mut parts = src.splitn(3, ':');
+        let (host, port, addrs) = match (parts.next(), parts.next(), parts.next()) {
+            (Some(host), Some(port), Some(addrs)) if !host.is_empty() => (host, port, addrs),
+            _ => return Err(format!("Invalid resolve {}, expected host:*:addr", src)),
+        };
+
+        // Unlike curl, the override can't be limited to a port
+        if port != "*" {
+            return Err(format!(
+                "Invalid port {} in resolve {}, the override applies to every port: write {}:*:{}",
+                port, src, host, addrs
+            ));
+        }
+
+        let addrs = addrs
+            .split(',')
+            .map(|addr| {
+                addr.trim_start_matches('[')
+                    .trim_end_matches(']')
+                    .parse::<IpAddr>()
+                    .map_err(|e| format!("Invalid address in resolve {}: {}", src, e))
+            })
+            .collect::<Result<Vec<IpAddr>, String>>()?;
+
+        Ok(HostOverride {
+            host: host.to_lowercase(),
+            addrs,
+        })
+    }
+}
+
+/// DNS resolver of the downloader. Overridden hosts are answered directly, the others are
+/// resolved by the system.
+/// With a network policy, the addresses it refuses are dropped from the answers
+pub struct Resolver {
+    overrides: HashMap<String, Vec<IpAddr>>,
+    policy: Option<NetworkPolicy>,
+}
+
+impl Resolver {
+    /// Create a resolver answering the given overrides, and filtering with the given policy
+    pub fn new(overrides: &[HostOverride], policy: Option<NetworkPolicy>) -> Resolver {
+        let mut map: HashMap<String, Vec<IpAddr>> = HashMap::new();
+        for host_override in overrides {
+            map.entry(host_override.host.clone())
+                .or_default()
+                .extend(&host_override.addrs);
+        }
+
+        Resolver {
+            overrides: map,
+            policy,
+        }
+    }
+}
+
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        // Port 0 lets the connector use the port of the URL
+        let overridden = self.overrides.get(name.as_str()).map(|addrs| {
+            addrs
+                .iter()
+                .map(|addr| SocketAddr::new(*addr, 0))
+                .collect::<Vec<SocketAddr>>()
+        });
+        let host = name.as_str().to_string();
+        let policy = self.policy.clone();
+
+        Box::pin(async move {
+            let mut addrs: Vec<SocketAddr> = match overridden {
+                Some(addrs) => addrs,
+                None => tokio::net::lookup_host((host.as_str(), 0)).await?.collect(),
+            };
+
+            if let Some(policy) = policy {
+                let refused = addrs.first().map(SocketAddr::ip);
+                addrs.retain(|addr| policy.is_allowed(&addr.ip()));
+
+                if let (true, Some(addr)) = (addrs.is_empty(), refused) {
+                    return Err(Box::new(RefusedAddress { host, addr }) as _);
+                }
+            }
+
+            Ok(Box::new(addrs.into_iter()) as Addrs)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_host_override() {
+        assert_eq!(
+            "Example.com:*:127.0.0.1".parse::<HostOverride>(),
+            Ok(HostOverride {
+                host: "example.com".to_string(),
+                addrs: vec!["127.0.0.1".parse().unwrap()],
+            })
+        );
+        assert_eq!(
+            "example.com:*:[::1],10.0.0.2".parse::<HostOverride>(),
+            Ok(HostOverride {
+                host: "example.com".to_string(),
+                addrs: vec!["::1".parse().unwrap(), "10.0.0.2".parse().unwrap()],
+            })
+        );
+    }
+
+    #[test]
+    fn parse_invalid_host_override() {
+        assert!("example.com".parse::<HostOverride>().is_err());
+        assert!("example.com:*".parse::<HostOverride>().is_err());
+        assert!(":*:127.0.0.1".parse::<HostOverride>().is_err());
+        assert!("example.com:*:localhost".parse::<HostOverride>().is_err());
+        // The override would apply to the other ports too
+        assert!("example.com:443:127.0.0.1".parse::<HostOverride>().is_err());
+    }
+}
@@ -1,23 +1,42 @@
 /// Separates HTML responses and other content (PDFs, images...)
 pub enum ResponseData {
     Html(Vec<u8>),
+    Css(Vec<u8>),
     Other(Vec<u8>),
 }
 
+impl ResponseData {
+    /// Return the size of the body in bytes
+    pub fn size(&self) -> usize {
+        match self {
+            ResponseData::Html(data) | ResponseData::Css(data) | ResponseData::Other(data) => {
+                data.len()
+            }
+        }
+    }
+}
+
 /// Wrapper around `ResponseData`
 pub struct Response {
     pub data: ResponseData,
     pub filename: Option<String>,
     pub charset: Option<String>,
+    pub status: u16,
 }
 
 impl Response {
     ///Create a new Response
-    pub fn new(data: ResponseData, filename: Option<String>, charset: Option<String>) -> Response {
+    pub fn new(
+        data: ResponseData,
+        filename: Option<String>,
+        charset: Option<String>,
+        status: u16,
+    ) -> Response {
         Response {
             data,
             filename,
             charset,
+            status,
         }
     }
 }
@@ -0,0 +1,100 @@
+use std::str::FromStr;
+
+use regex::Regex;
+use url::Url;
+
+/// A rule rewriting the URLs matching a regex before they are fetched, like
+/// `^https://example\.com/(.*)$ https://mirror.example.com/$1`
+#[derive(Debug, Clone)]
+pub struct RewriteRule {
+    pattern: Regex,
+    replacement: String,
+}
+
+impl FromStr for RewriteRule {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<RewriteRule, String> {
+        let (pattern, replacement) =
+            src.trim().split_once(char::is_whitespace).ok_or_else(|| {
+                format!(
+                    "Invalid rewrite rule {}, expected <regex> <replacement>",
+                    src
+                )
+            })?;
+        let pattern = Regex::new(pattern).map_err(|e| e.to_string())?;
+
+        Ok(RewriteRule {
+            pattern,
+            replacement: replacement.trim().to_string(),
+        })
+    }
+}
+
+/// Rewrite an URL with the first rule matching it. Return `None` if no rule matches, and an
+/// error if the rewritten URL is invalid
+pub fn rewrite(rules: &[RewriteRule], url: &Url) -> Result<Option<Url>, String> {
+    let rule = match rules
+        .iter()
+        .find(|rule| rule.pattern.is_match(url.as_str()))
+    {
+        Some(rule) => rule,
+        None => return Ok(None),
+    };
+
+    let rewritten = rule
+        .pattern
+        .replace(url.as_str(), rule.replacement.as_str());
+    match Url::parse(&rewritten) {
+        Ok(rewritten) if rewritten.host_str().is_some() => Ok(Some(rewritten)),
+        Ok(_) => Err(format!("the rewritten URL {} has no host", rewritten)),
+        Err(e) => Err(format!("the rewritten URL {} is invalid: {}", rewritten, e)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rules(rules: &[&str]) -> Vec<RewriteRule> {
+        rules.iter().map(|rule| rule.parse().unwrap()).collect()
+    }
+
+    fn rewritten(rules: &[RewriteRule], url: &str) -> Option<String> {
+        rewrite(rules, &Url::parse(url).unwrap())
+            .unwrap()
+            .map(|url| url.to_string())
+    }
+
+    #[test]
+    fn first_match() {
+        let rules = rules(&[
+            r"^https://cdn\.example\.com/(.*)$ http://intranet.local/cdn/$1",
+            r"^(https://example\.com/articles/[^?]*)$ $1?format=print",
+            r"^https://example\.com/(.*)$ http://intranet.local/$1",
+        ]);
+
+        assert_eq!(
+            rewritten(&rules, "https://cdn.example.com/img/a.png"),
+            Some("http://intranet.local/cdn/img/a.png".to_string())
+        );
+        assert_eq!(
+            rewritten(&rules, "https://example.com/articles/1.html"),
+            Some("https://example.com/articles/1.html?format=print".to_string())
+        );
+        assert_eq!(
+            rewritten(&rules, "https://example.com/about/"),
+            Some("http://intranet.local/about/".to_string())
+        );
+        assert_eq!(rewritten(&rules, "https://other.com/"), None);
+    }
+
+    #[test]
+    fn invalid() {
+        assert!("^https://example.com/".parse::<RewriteRule>().is_err());
+        assert!("( $1".parse::<RewriteRule>().is_err());
+
+        let rules = rules(&["^https://example.com/(.*)$ $1"]);
+        assert!(rewrite(&rules, &Url::parse("https://example.com/a").unwrap()).is_err());
+    }
+}
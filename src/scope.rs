@@ -0,0 +1,269 @@
+use std::str::FromStr;
+
+use url::Url;
+
+/// Which hosts belong to the site being scraped. Links inside the scope are followed up to
+/// `--depth`, the others up to `--ext-depth`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Scope {
+    /// The exact host of the page containing the link
+    Host,
+    /// The registrable domain of the page containing the link, like `example.co.uk`
+    Domain,
+    /// A domain and all its subdomains
+    SubdomainsOf(String),
+    /// An explicit list of hosts
+    List(Vec<String>),
+}
+
+impl FromStr for Scope {
+    type Err = String;
+
+    fn from_str(src: &str) -> Result<Scope, String> {
+        match src.split_once(':') {
+            None if src == "host" => Ok(Scope::Host),
+            None if src == "domain" => Ok(Scope::Domain),
+            Some(("subdomains-of", domain)) if !domain.is_empty() => Ok(Scope::SubdomainsOf(
+                domain.trim_matches('.').to_lowercase(),
+            )),
+            Some(("list", hosts)) if !hosts.is_empty() => Ok(Scope::List(
+                hosts
+                    .split(',')
+                    .map(|host| host.trim().to_lowercase())
+                    .filter(|host| !host.is_empty())
+                    .collect(),
+            )),
+            _ => Err(format!(
+                "Invalid scope {}, expected host, domain, subdomains-of:<domain> or list:<host>,<host>...",
+                src
+            )),
+        }
+    }
+}
+
+/// Return the registrable domain of a host, or the host itself if it has none, like IPs
+fn registrable_domain(host: &str) -> &str {
+    psl::domain_str(host).unwrap_or(host)
+}
+
+/// Check if a host is a domain or one of its subdomains
+fn is_subdomain_of(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+impl Scope {
+    /// Check if `url`, linked from the page at `base`, is part of the site
+    pub fn contains(&self, url: &Url, base: &Url) -> bool {
+        let host = match url.host_str() {
+            Some(host) => host,
+            None => return true,
+        };
+
+        match self {
+            Scope::Host => Some(host) == base.host_str(),
+            Scope::Domain => base
+                .host_str()
+                .is_some_and(|base_host| registrable_domain(host) == registrable_domain(base_host)),
+            Scope::SubdomainsOf(domain) => is_subdomain_of(host, domain),
+            Scope::List(hosts) => hosts.iter().any(|listed| listed == host),
+        }
+    }
+}
+
+/// URL prefixes confining the scraping, like wget's `--no-parent`. URLs sharing the scheme,
+/// host and port of a prefix must start with one of those prefixes, others are not restricted
+#[derive(Debug, Clone, PartialEq)]
+pub struct PathPrefixes {
+    prefixes: Vec<Url>,
+}
+
+impl PathPrefixes {
+    /// Gather the directory of the origin if `no_parent` is set, and the explicit prefixes.
+    /// The latter can be absolute URLs or paths on the host of the origin
+    pub fn new(origin: &Url, no_parent: bool, explicit: &[String]) -> Result<PathPrefixes, String> {
+        let mut prefixes = Vec::new();
+
+        if no_parent {
+            // Like wget, the last segment of the path is a file unless it ends with a slash
+            prefixes.push(origin.join("./").map_err(|e| e.to_string())?);
+        }
+
+        for prefix in explicit {
+            let url = match Url::parse(prefix) {
+                Ok(url) => url,
+                Err(_) if prefix.starts_with('/') => {
+                    origin.join(prefix).map_err(|e| e.to_string())?
+                }
+                Err(e) => return Err(format!("Invalid scope prefix {}: {}", prefix, e)),
+            };
+            prefixes.push(url);
+        }
+
+        Ok(PathPrefixes { prefixes })
+    }
+
+    /// Check if an URL is under one of the prefixes of its host
+    pub fn contains(&self, url: &Url) -> bool {
+        let mut same_origin = self
+            .prefixes
+            .iter()
+            .filter(|prefix| prefix.origin() == url.origin())
+            .peekable();
+
+        same_origin.peek().is_none()
+            || same_origin.any(|prefix| {
+                url.path().starts_with(prefix.path())
+                    // The directory itself, when the trailing slash is missing
+                    || prefix.path().strip_suffix('/') == Some(url.path())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contains(scope: &str, url: &str, base: &str) -> bool {
+        scope
+            .parse::<Scope>()
+            .unwrap()
+            .contains(&Url::parse(url).unwrap(), &Url::parse(base).unwrap())
+    }
+
+    #[test]
+    fn parse_scope() {
+        assert_eq!("host".parse(), Ok(Scope::Host));
+        assert_eq!("domain".parse(), Ok(Scope::Domain));
+        assert_eq!(
+            "subdomains-of:.Example.com".parse(),
+            Ok(Scope::SubdomainsOf("example.com".to_string()))
+        );
+        assert_eq!(
+            "list:a.com, B.org".parse(),
+            Ok(Scope::List(vec!["a.com".to_string(), "b.org".to_string()]))
+        );
+        assert!("hosts".parse::<Scope>().is_err());
+        assert!("subdomains-of:".parse::<Scope>().is_err());
+    }
+
+    #[test]
+    fn host_scope() {
+        assert!(contains(
+            "host",
+            "https://example.com/a",
+            "https://example.com/"
+        ));
+        assert!(!contains(
+            "host",
+            "https://docs.example.com/",
+            "https://example.com/"
+        ));
+    }
+
+    #[test]
+    fn domain_scope() {
+        assert!(contains(
+            "domain",
+            "https://cdn.example.com/",
+            "https://docs.example.com/"
+        ));
+        assert!(contains(
+            "domain",
+            "https://a.example.co.uk/",
+            "https://example.co.uk/"
+        ));
+        assert!(!contains(
+            "domain",
+            "https://other.co.uk/",
+            "https://example.co.uk/"
+        ));
+        // Each user of a shared public suffix is its own site
+        assert!(!contains(
+            "domain",
+            "https://bob.github.io/",
+            "https://alice.github.io/"
+        ));
+    }
+
+    #[test]
+    fn subdomains_of_scope() {
+        let scope = "subdomains-of:example.com";
+        assert!(contains(scope, "https://example.com/", "https://a.org/"));
+        assert!(contains(
+            scope,
+            "https://a.b.example.com/",
+            "https://a.org/"
+        ));
+        assert!(!contains(
+            scope,
+            "https://badexample.com/",
+            "https://a.org/"
+        ));
+        assert!(!contains(
+            scope,
+            "https://example.com.evil/",
+            "https://a.org/"
+        ));
+    }
+
+    #[test]
+    fn no_parent() {
+        let origin = Url::parse("https://example.com/docs/v2/index.html").unwrap();
+        let prefixes = PathPrefixes::new(&origin, true, &[]).unwrap();
+        let contains = |url| prefixes.contains(&Url::parse(url).unwrap());
+
+        assert!(contains("https://example.com/docs/v2/"));
+        assert!(contains("https://example.com/docs/v2"));
+        assert!(contains("https://example.com/docs/v2/api/types.html"));
+        assert!(!contains("https://example.com/docs/"));
+        assert!(!contains("https://example.com/docs/v20/"));
+        assert!(!contains("https://example.com/"));
+        // Other hosts are left to the scope
+        assert!(contains("https://cdn.example.com/style.css"));
+        assert!(contains("http://example.com/"));
+    }
+
+    #[test]
+    fn explicit_prefixes() {
+        let origin = Url::parse("https://example.com/docs/v2/").unwrap();
+        let prefixes = PathPrefixes::new(
+            &origin,
+            true,
+            &[
+                "/static/".to_string(),
+                "https://cdn.example.com/docs/".to_string(),
+            ],
+        )
+        .unwrap();
+        let contains = |url| prefixes.contains(&Url::parse(url).unwrap());
+
+        assert!(contains("https://example.com/docs/v2/intro.html"));
+        assert!(contains("https://example.com/static/style.css"));
+        assert!(!contains("https://example.com/blog/"));
+        assert!(contains("https://cdn.example.com/docs/logo.png"));
+        assert!(!contains("https://cdn.example.com/other/logo.png"));
+
+        assert!(PathPrefixes::new(&origin, false, &[])
+            .unwrap()
+            .contains(&origin.join("/").unwrap()));
+        assert!(PathPrefixes::new(&origin, false, &["static".to_string()]).is_err());
+    }
+
+    #[test]
+    fn list_scope() {
+        let scope = "list:example.com,cdn.example.net";
+        assert!(contains(
+            scope,
+            "https://cdn.example.net/",
+            "https://a.org/"
+        ));
+        assert!(!contains(
+            scope,
+            "https://www.example.com/",
+            "https://a.org/"
+        ));
+    }
+}
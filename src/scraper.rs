@@ -1,11 +1,8 @@
 use std::borrow::Borrow;
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::path::Path;
-use std::sync::Mutex;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Once};
 use std::time;
 
-use crossbeam::channel::{Receiver, Sender, TryRecvError};
 use crossbeam::thread;
 use encoding_rs::Encoding;
 use lazy_static::lazy_static;
@@ -17,19 +14,33 @@ use url::Url;
 use crate::{error, info, warn};
 
 use super::args;
+use super::budget::Budget;
+use super::canonical::Canonicalizer;
+use super::content_filter::ContentFilter;
+use super::css;
+use super::depth::{self, DepthLimit};
 use super::disk;
 use super::dom;
 use super::downloader;
+use super::filter::{Filter, Rules};
+use super::frontier::{Frontier, Job};
 use super::response;
+use super::rewrite;
+use super::scope::PathPrefixes;
+use super::simhash::{self, SimHashIndex};
+use super::soft404::Soft404Detector;
+use super::state::{self, SeenUrls};
+use super::summary::Summary;
+use super::trap::TrapDetector;
 use super::url_helper;
 
-/// Maximum number of empty recv() from the channel
+/// Maximum number of times the frontier is found empty in a row
 static MAX_EMPTY_RECEIVES: usize = 10;
 
 /// If args.depth is this, it will download everything
 static INFINITE_DEPTH: i32 = -1;
 
-/// Sleep duration on empty recv()
+/// Sleep duration when the frontier is empty
 static SLEEP_MILLIS: u64 = 500;
 static SLEEP_DURATION: time::Duration = time::Duration::from_millis(SLEEP_MILLIS);
 
@@ -37,68 +48,329 @@ static SLEEP_DURATION: time::Duration = time::Duration::from_millis(SLEEP_MILLIS
 /// adds more as new URLs are found
 pub struct Scraper {
     args: args::Args,
-    transmitter: Sender<(Url, i32, i32)>,
-    receiver: Receiver<(Url, i32, i32)>,
+    frontier: Frontier,
     downloader: downloader::Downloader,
-    visited_urls: Mutex<HashSet<String>>,
-    path_map: Mutex<HashMap<String, String>>,
+    seen: SeenUrls,
+    prefixes: PathPrefixes,
+    canonicalizer: Canonicalizer,
+    traps: TrapDetector,
+    summary: Summary,
+    near_duplicates: Option<SimHashIndex>,
+    soft_404s: Option<Soft404Detector>,
+    content_filter: ContentFilter,
+    visit_filter: Filter,
+    download_filter: Filter,
+    budget: Arc<Budget>,
+}
+
+/// An HTML page processed for offline navigation
+enum HtmlPage {
+    /// A page whose links were followed
+    Expanded(Vec<u8>),
+    /// A near-duplicate of an already downloaded page, whose links were not followed
+    NearDuplicate(Vec<u8>, simhash::Page),
 }
 
 impl Scraper {
     /// Create a new scraper with command line options
     pub fn new(args: args::Args) -> Scraper {
-        let (tx, rx) = crossbeam::channel::unbounded();
+        let read_rules = |files: &[PathBuf]| -> Vec<Rules> {
+            files
+                .iter()
+                .map(|file| Rules::from_file(file).unwrap_or_else(|e| error!("{}", e)))
+                .collect()
+        };
+        let download_filter = Filter::new(
+            &args.include_download,
+            &args.exclude_download,
+            &args.accept,
+            &args.reject,
+            read_rules(&args.download_rules),
+        )
+        .unwrap_or_else(|e| error!("{}", e));
+        let visit_filter = if args.visit_filter_is_download_filter {
+            download_filter.clone()
+        } else {
+            Filter::new(
+                &args.include_visit,
+                &args.exclude_visit,
+                &[],
+                &[],
+                read_rules(&args.visit_rules),
+            )
+            .unwrap_or_else(|e| error!("{}", e))
+        };
 
-        let mut args = args;
-        if args.visit_filter_is_download_filter {
-            args.include_visit = args.include_download.clone();
-            args.exclude_visit = args.exclude_download.clone();
-        }
+        let canonicalizer = Canonicalizer::new(&args);
+
+        // The prefixes are compared to canonical URLs
+        let scope_prefixes: Vec<String> = args
+            .scope_prefix
+            .iter()
+            .map(|prefix| match Url::parse(prefix) {
+                Ok(url) => canonicalizer.canonicalize(&url).to_string(),
+                Err(_) => prefix.clone(),
+            })
+            .collect();
+        let prefixes = PathPrefixes::new(
+            &canonicalizer.canonicalize(&args.origin),
+            args.no_parent,
+            &scope_prefixes,
+        )
+        .unwrap_or_else(|e| error!("{}", e));
+
+        let budget = Arc::new(Budget::new(&args));
+        let downloader = downloader::Downloader::new(&args);
+        let downloader = if args.abort_in_flight {
+            downloader.with_budget(budget.clone())
+        } else {
+            downloader
+        };
 
-        Scraper {
-            downloader: downloader::Downloader::new(
-                args.tries,
-                &args.user_agent,
-                &args.cookie,
-                args.disable_certs_checks,
-                &args.auth,
-                &args.origin,
+        let database = args
+            .state_dir
+            .as_ref()
+            .map(|dir| state::open_database(dir).unwrap_or_else(|e| error!("{}", e)));
+        let frontier = Frontier::new(
+            args.strategy,
+            args.priority.clone(),
+            args.max_jobs_per_host,
+            database
+                .as_ref()
+                .map(|database| state::open_tree(database, "frontier")),
+        );
+        let seen = match &database {
+            Some(database) => SeenUrls::with_database(database, args.bloom_filter),
+            None => SeenUrls::new(),
+        };
+
+        // Without --state-dir, the URLs go to a temporary database once the memory holds too many
+        let (frontier, seen) = match args.max_urls_in_memory {
+            Some(max_urls) if database.is_none() => (
+                frontier.with_memory_limit(max_urls, std::env::temp_dir()),
+                seen.with_memory_limit(max_urls, std::env::temp_dir(), args.bloom_filter),
             ),
+            _ => (frontier, seen),
+        };
+
+        Scraper {
+            prefixes,
+            canonicalizer,
+            traps: TrapDetector::new(&args),
+            summary: Summary::new(),
+            near_duplicates: args.near_duplicate_distance.map(SimHashIndex::new),
+            soft_404s: args.detect_soft_404s.then(Soft404Detector::new),
+            content_filter: ContentFilter::new(&args),
+            visit_filter,
+            download_filter,
+            budget,
+            downloader,
+            frontier,
+            seen,
             args,
-            transmitter: tx,
-            receiver: rx,
-            visited_urls: Mutex::new(HashSet::new()),
-            path_map: Mutex::new(HashMap::new()),
         }
     }
 
-    /// Add an URL to the path_map HashMap and return if it was inserted or not
-    fn map_url_path(&self, url: &Url, path: String) -> bool {
-        let mut path_map = self.path_map.lock().unwrap();
+    /// Return the key of an URL in the seen URLs: its canonical form, without fragment
+    fn seen_key(&self, url: &Url) -> Url {
+        let mut key = self.canonicalizer.canonicalize(url);
+        key.set_fragment(None);
+        key
+    }
+
+    /// Add the canonical form of an URL to the seen URLs, with its path on the disk named
+    /// after `name_url`, and return if it was inserted or not
+    fn map_url_path(&self, url: &Url, name_url: &Url) -> bool {
+        let key = self.seen_key(url);
+        let path = url_helper::to_path(&self.seen_key(name_url), false);
 
-        if !path_map.contains_key(url.as_str()) {
-            path_map.insert(url.to_string(), path);
-            true
-        } else {
-            false
-        }
+        self.seen.insert(key.as_str(), &path)
     }
 
-    /// Push a new URL into the channel
-    fn push(transmitter: &Sender<(Url, i32, i32)>, url: Url, depth: i32, ext_depth: i32) {
-        if let Err(e) = transmitter.send((url, depth, ext_depth)) {
-            error!("Couldn't push to channel ! {}", e);
-        }
+    /// Return the path on the disk of an URL added to the seen URLs
+    fn get_path(&self, url: &Url) -> Option<String> {
+        let key = self.seen_key(url);
+
+        self.seen.path(key.as_str())
     }
 
-    /// Fix the URLs contained in the DOM-tree so they point to each other relatively
-    fn fix_domtree(&self, dom_url: &mut String, source_path: &str, dest_path: &str) {
+    /// Reserve a path in the host directory of `url` for a file name suggested by the server.
+    /// A counter is appended to the name if it is already used by another file
+    fn claim_filename(&self, url: &Url, filename: &str, path: &str) -> String {
+        let canonical_url = self.canonicalizer.canonicalize(url);
+        let host = canonical_url.host_str().unwrap();
+        let (stem, extension) = match filename.rfind('.') {
+            Some(index) if index > 0 => filename.split_at(index),
+            _ => (filename, ""),
+        };
+
+        self.seen.claim_path(path, |counter| match counter {
+            0 => format!("{}/{}", host, filename),
+            counter => format!("{}/{}_{}{}", host, stem, counter, extension),
+        })
+    }
+
+    /// Push a new URL into the frontier
+    fn push(&self, url: Url, kind: dom::LinkKind, depth: i32, ext_depth: i32) {
+        let job = Job {
+            url,
+            depth,
+            ext_depth,
+        };
+        self.frontier.push(job, kind == dom::LinkKind::Requisite);
+    }
+
+    /// Return the path of `dest_path` relative to the directory of `source_path`
+    fn relative_path(source_path: &str, dest_path: &str) -> String {
         let source_path_parent = Path::new(source_path).parent().unwrap().to_str().unwrap(); //Unwrap should be safe, there will alway be at least .../index.html
         let diff_path = pathdiff::diff_paths(dest_path, source_path_parent).unwrap();
-        let relative_path = diff_path.as_path().to_str().unwrap();
+
+        diff_path.as_path().to_str().unwrap().to_string()
+    }
+
+    /// Fix the URLs contained in the DOM-tree so they point to each other relatively
+    fn fix_domtree(&self, dom_url: &mut String, source_path: &str, dest_path: &str) {
+        let relative_path = Scraper::relative_path(source_path, dest_path);
 
         dom_url.clear();
-        dom_url.push_str(relative_path);
+        dom_url.push_str(&relative_path);
+    }
+
+    /// Return the absolute URL of a link found in the page at `page`
+    fn resolve_link(page: &Url, link: &str) -> Url {
+        let url_to_parse = Scraper::normalize_url(link.to_string());
+
+        match page.join(url_to_parse.as_str()) {
+            Ok(url) => url,
+            Err(e) => panic!("Failed to parse url: {} | Error: {}", link, e),
+        }
+    }
+
+    /// Return the URL fetched for a link, rewritten by the first matching rewrite rule, and a
+    /// function reporting the rewrite
+    fn rewrite_url<'a>(&'a self, url: &'a Url) -> (Url, impl FnOnce() + 'a) {
+        let rewritten = rewrite::rewrite(&self.args.rewrite, url);
+        let fetched_url = match &rewritten {
+            Ok(Some(rewritten)) => rewritten.clone(),
+            _ => url.clone(),
+        };
+
+        let report = move || match rewritten {
+            Ok(Some(rewritten)) if self.args.verbose => {
+                info!("Rewriting {} to {}", url, rewritten)
+            }
+            Err(e) => warn!("Not rewriting {}, {}", url, e),
+            _ => (),
+        };
+
+        (fetched_url, report)
+    }
+
+    /// Return the URL naming the file of a link on the disk: the fetched URL, or the original
+    /// one with --rewrite-keep-paths. Invalid rewrites are reported when the link is queued
+    fn name_url(&self, url: &Url) -> Url {
+        if self.args.rewrite_keep_paths {
+            return url.clone();
+        }
+
+        match rewrite::rewrite(&self.args.rewrite, url) {
+            Ok(Some(rewritten)) => rewritten,
+            _ => url.clone(),
+        }
+    }
+
+    /// Return the path on the disk of an URL linked by a page. Equivalent URLs share the
+    /// path of their canonical form
+    fn link_path(&self, url: &Url) -> String {
+        url_helper::to_path(&self.canonicalizer.canonicalize(&self.name_url(url)), true)
+    }
+
+    /// Push an URL linked by the page at `page`, after the rewrite rules, if its canonical form
+    /// was not seen yet and it is within the depth limits, the ones of the first matching depth
+    /// rule for internal pages. Requisites are needed to display the page, so they don't
+    /// consume any depth
+    fn queue_link(&self, page: &Url, url: Url, kind: dom::LinkKind, depth: i32, ext_depth: i32) {
+        // The first URL seen of its canonical form is the one downloaded, and its rewrite is
+        // only reported then
+        let (fetched_url, report_rewrite) = self.rewrite_url(&url);
+        if !self.map_url_path(&fetched_url, &self.name_url(&url)) {
+            return;
+        }
+        report_rewrite();
+
+        let canonical_url = self.canonicalizer.canonicalize(&url);
+        let canonical_base = self.canonicalizer.canonicalize(page);
+
+        // The depths of the link once queued, if it is followed
+        let depths = if kind == dom::LinkKind::Requisite {
+            Some((depth, ext_depth))
+        } else if self.args.page {
+            // Only the frames of the origin page are followed
+            Some((depth + 1, ext_depth))
+        } else if self.args.scope.contains(&canonical_url, &canonical_base) {
+            // If we are determining for a local domain
+            let max_depth = match depth::find_limit(&self.args.depth_rule, &url) {
+                Some(DepthLimit::Max(max_depth)) => max_depth,
+                _ => self.args.depth,
+            };
+            (max_depth == INFINITE_DEPTH || depth < max_depth).then(|| (depth + 1, ext_depth))
+        } else {
+            // If we are determining for an external domain
+            (self.args.ext_depth == INFINITE_DEPTH || ext_depth < self.args.ext_depth)
+                .then(|| (depth, ext_depth + 1))
+        };
+        let (depth, ext_depth) = match depths {
+            Some(depths) => depths,
+            None => return,
+        };
+
+        // Only the links which would be queued count in the budgets and the trap patterns
+        if let Err(exhausted) = self.budget.check(&fetched_url, true) {
+            self.summary.add_exhausted_budget(exhausted);
+        } else if let Err(reason) = self.traps.check(&canonical_url) {
+            warn!("Skipping {}, it looks like a crawler trap: {}", url, reason);
+            self.summary.add_trap(&url, reason);
+        } else {
+            self.push(fetched_url, kind, depth, ext_depth);
+        }
+    }
+
+    /// Check if a link of a page at `depth` is followed. With `--page`, the links other than
+    /// the requisites and the frames of the origin page are left to the server
+    fn is_followed(&self, kind: dom::LinkKind, depth: i32) -> bool {
+        !self.args.page
+            || kind == dom::LinkKind::Requisite
+            || (kind == dom::LinkKind::Frame && depth == 0)
+    }
+
+    /// Make a relative link of the page at `page` absolute, so it points to the server
+    fn make_absolute(page: &Url, link: &mut String) {
+        if link.is_empty() || link.starts_with('#') || Url::parse(link).is_ok() {
+            return;
+        }
+
+        if let Ok(url) = page.join(link) {
+            link.clear();
+            link.push_str(url.as_str());
+        }
+    }
+
+    /// Fix the URLs of a stylesheet of the page at `page` so they point to the files relatively,
+    /// `follow` giving the path of each of them
+    fn rewrite_css<F>(&self, css: &[u8], page: &Url, source_path: &str, mut follow: F) -> Vec<u8>
+    where
+        F: FnMut(&str) -> String,
+    {
+        css::rewrite_urls(css, |link| {
+            // References to the elements of SVG documents
+            if link.starts_with('#')
+                || !Scraper::should_visit(self, link, dom::LinkKind::Requisite, page)
+            {
+                return None;
+            }
+
+            Some(Scraper::relative_path(source_path, &follow(link)))
+        })
     }
 
     /// Find the charset of the webpage. ``data`` is not a String as this might not be utf8.
@@ -141,16 +413,16 @@ impl Scraper {
         !matches!(charset, "utf-8")
     }
 
-    /// Proces an html file: add new url to the chanel and prepare for offline navigation
+    /// Proces an html file: add new url to the frontier and prepare for offline navigation
     fn handle_html(
         scraper: &Scraper,
-        transmitter: &Sender<(Url, i32, i32)>,
         url: &Url,
         depth: i32,
         ext_depth: i32,
         data: &[u8],
         http_charset: Option<String>,
-    ) -> Vec<u8> {
+        saved: bool,
+    ) -> HtmlPage {
         let charset_source_str = match Self::find_charset(data, http_charset) {
             Some(s) => s,
             None => {
@@ -179,99 +451,234 @@ impl Scraper {
         };
 
         let dom = dom::Dom::new(&String::from_utf8_lossy(&utf8_data));
-        let source_path = match scraper.path_map.lock().unwrap().get(url.as_str()) {
-            Some(path) => path.clone(),
+        let source_path = match scraper.get_path(url) {
+            Some(path) => path,
             None => error!("Url {} was not found in the path map", url.as_str()),
         };
 
-        dom.find_urls_as_strings()
-            .into_iter()
-            .filter(|candidate| Scraper::should_visit(scraper, candidate))
-            .for_each(|next_url| {
-                let url_to_parse = Scraper::normalize_url(next_url.clone());
-
-                let next_full_url = match url.join(url_to_parse.as_str()) {
-                    Ok(url) => url,
-                    Err(e) => panic!("Failed to parse url: {} | Error: {}", next_url, e),
-                };
+        // Only the saved pages are recorded, as the near-duplicates are linked to them
+        let original = scraper.near_duplicates.as_ref().and_then(|index| {
+            let fingerprint = simhash::fingerprint(&dom.text())?;
+            if !saved {
+                return index.find(fingerprint);
+            }
+            index.find_or_insert(
+                fingerprint,
+                simhash::Page {
+                    url: url.to_string(),
+                    path: source_path.clone(),
+                },
+            )
+        });
+        if let Some(original) = &original {
+            if scraper.args.verbose {
+                info!(
+                    "Not following the links of {}, it is a near-duplicate of {}",
+                    url, original.url
+                );
+            }
+        }
 
-                let path = url_helper::to_path(&next_full_url, true);
-                let path_no_fragments = url_helper::to_path(&next_full_url, false);
+        let no_follow =
+            depth::find_limit(&scraper.args.depth_rule, url) == Some(DepthLimit::NoFollow);
 
-                // We only add urls without fragments to avoid duplication
-                if scraper.map_url_path(&next_full_url, path_no_fragments.clone()) {
-                    if !Scraper::is_on_another_domain(next_url, url) {
-                        // If we are determining for a local domain
-                        if scraper.args.depth == INFINITE_DEPTH || depth < scraper.args.depth {
-                            Scraper::push(transmitter, next_full_url, depth + 1, ext_depth);
-                        }
-                    } else {
-                        // If we are determining for an external domain
-                        if scraper.args.ext_depth == INFINITE_DEPTH
-                            || ext_depth < scraper.args.ext_depth
-                        {
-                            Scraper::push(transmitter, next_full_url, depth, ext_depth + 1);
-                        }
-                    }
-                }
+        // The links of near-duplicates and nofollow pages are fixed, but not followed
+        let follow = |kind: dom::LinkKind, link: &str| {
+            let next_full_url = Scraper::resolve_link(url, link);
+            let path = scraper.link_path(&next_full_url);
+            if original.is_none() && (kind == dom::LinkKind::Requisite || !no_follow) {
+                scraper.queue_link(url, next_full_url, kind, depth, ext_depth);
+            }
+            path
+        };
 
+        for (kind, next_url) in dom.find_links() {
+            if scraper.is_followed(kind, depth)
+                && Scraper::should_visit(scraper, next_url, kind, url)
+            {
+                let path = follow(kind, next_url);
                 scraper.fix_domtree(next_url, &source_path, &path);
+            } else if scraper.args.page {
+                Scraper::make_absolute(url, next_url);
+            }
+        }
+
+        for style in dom.find_styles() {
+            let css = scraper.rewrite_css(style.as_bytes(), url, &source_path, |link| {
+                follow(dom::LinkKind::Requisite, link)
             });
+            *style = String::from_utf8(css).unwrap();
+        }
 
         let utf8_data = dom.serialize().into_bytes();
 
-        if need_charset_conversion {
+        let data = if need_charset_conversion {
             Self::charset_convert(&utf8_data, charset_utf8, charset_source)
         } else {
             utf8_data
+        };
+
+        match original {
+            Some(original) => HtmlPage::NearDuplicate(data, original),
+            None => HtmlPage::Expanded(data),
         }
     }
 
-    /// Process a single URL
-    fn handle_url(
+    /// Process a stylesheet: add the resources it uses to the frontier and prepare for offline
+    /// navigation
+    fn handle_css(
         scraper: &Scraper,
-        transmitter: &Sender<(Url, i32, i32)>,
-        url: Url,
+        url: &Url,
         depth: i32,
         ext_depth: i32,
-    ) {
-        let download_filter_matches = !scraper.args.exclude_download.is_match(url.as_str())
-            && scraper.args.include_download.is_match(url.as_str());
+        data: &[u8],
+    ) -> Vec<u8> {
+        let source_path = match scraper.get_path(url) {
+            Some(path) => path,
+            None => error!("Url {} was not found in the path map", url.as_str()),
+        };
+
+        scraper.rewrite_css(data, url, &source_path, |link| {
+            let next_full_url = Scraper::resolve_link(url, link);
+            let path = scraper.link_path(&next_full_url);
+            scraper.queue_link(
+                url,
+                next_full_url,
+                dom::LinkKind::Requisite,
+                depth,
+                ext_depth,
+            );
+            path
+        })
+    }
+
+    /// Check if a response is a "page not found" served with a 200 status
+    fn is_soft_404(&self, url: &Url, response: &response::Response) -> bool {
+        match (&self.soft_404s, &response.data) {
+            (Some(detector), response::ResponseData::Html(data)) if response.status == 200 => {
+                detector.is_soft_404(&self.downloader, url, data)
+            }
+            _ => false,
+        }
+    }
+
+    /// Process a single URL
+    fn handle_url(scraper: &Scraper, url: Url, depth: i32, ext_depth: i32) {
+        // The URLs queued before a budget was exhausted are skipped
+        if let Err(exhausted) = scraper.budget.start(&url) {
+            if scraper.args.verbose {
+                info!("Skipping {}, {}", url, exhausted);
+            }
+            scraper.summary.add_exhausted_budget(exhausted);
+            return;
+        }
+
+        let mut download_filter_matches = scraper.download_filter.is_match(&url);
         // download the page even if the download filter does not match,
         // so its links can be discovered and added to the queue
-        match scraper.downloader.get(&url) {
+        let result = scraper.downloader.get(&url);
+        if let Ok(response) = &result {
+            scraper.budget.add_bytes(&url, response.data.size() as u64);
+        }
+
+        match result {
+            Ok(response) if scraper.is_soft_404(&url, &response) => {
+                warn!("Skipping {}, it looks like a page not found", url);
+                scraper.summary.add_broken_link(&url, "soft 404");
+            }
             Ok(response) => {
-                let data = match response.data {
-                    response::ResponseData::Html(data) => Scraper::handle_html(
+                if response.status >= 400 {
+                    scraper
+                        .summary
+                        .add_broken_link(&url, &format!("HTTP status {}", response.status));
+                }
+
+                // Pages are downloaded anyway for their links, the content filters only decide
+                // if they are saved
+                if let response::ResponseData::Html(data) = &response.data {
+                    download_filter_matches &=
+                        scraper.content_filter.check_page(data.len() as u64).is_ok();
+                }
+
+                let (data, original) = match response.data {
+                    response::ResponseData::Html(data) => match Scraper::handle_html(
                         scraper,
-                        transmitter,
                         &url,
                         depth,
                         ext_depth,
                         &data,
                         response.charset,
+                        !scraper.args.dry_run && download_filter_matches,
+                    ) {
+                        HtmlPage::Expanded(data) => (data, None),
+                        HtmlPage::NearDuplicate(data, original) => (data, Some(original)),
+                    },
+                    response::ResponseData::Css(data) => (
+                        Scraper::handle_css(scraper, &url, depth, ext_depth, &data),
+                        None,
                     ),
-                    response::ResponseData::Other(data) => data,
+                    response::ResponseData::Other(data) => (data, None),
                 };
 
-                // Create a scope to unlock path_map automagicly
-                {
-                    let path_map = scraper.path_map.lock().unwrap();
-                    let path = path_map.get(url.as_str()).unwrap();
+                let path = scraper.get_path(&url).unwrap();
+
+                if let Some(original) = &original {
+                    scraper.summary.add_near_duplicate(&url, &original.url);
+                }
 
-                    if !scraper.args.dry_run && download_filter_matches {
-                        match response.filename {
-                            Some(filename) => {
-                                disk::save_file(&filename, &data, &scraper.args.output);
-                                disk::symlink(path, &filename, &scraper.args.output);
+                if !scraper.args.dry_run && download_filter_matches {
+                    match (original, response.filename) {
+                        (Some(original), _) => {
+                            if scraper.args.no_symlinks {
+                                disk::save_file(&path, &data, &scraper.args.output);
+                            } else {
+                                disk::symlink(&path, &original.path, &scraper.args.output);
                             }
-                            None => {
-                                disk::save_file(path, &data, &scraper.args.output);
+                        }
+                        (None, Some(filename)) => {
+                            let filename = scraper.claim_filename(&url, &filename, &path);
+                            disk::save_file(&filename, &data, &scraper.args.output);
+
+                            if filename != path {
+                                if scraper.args.no_symlinks {
+                                    disk::save_file(&path, &data, &scraper.args.output);
+                                } else {
+                                    disk::symlink(&path, &filename, &scraper.args.output);
+                                }
                             }
                         }
+                        (None, None) => {
+                            disk::save_file(&path, &data, &scraper.args.output);
+                        }
                     }
                 }
             }
+            Err(downloader::DownloadError::Rejected(rejection)) => {
+                download_filter_matches = false;
+                if scraper.args.verbose {
+                    info!("Skipping {}, {}", url, rejection);
+                }
+            }
+            Err(downloader::DownloadError::Aborted(exhausted)) => {
+                download_filter_matches = false;
+                if scraper.args.verbose {
+                    info!("Aborted the download of {}, {}", url, exhausted);
+                }
+                scraper.summary.add_exhausted_budget(exhausted);
+            }
+            Err(downloader::DownloadError::NotCached) => {
+                warn!("Skipping {}, it is not in the cache", url);
+            }
+            Err(e) if e.is_refused() => {
+                warn!("Refused to download {}: {}", url, e);
+            }
+            Err(e) if e.is_timeout() => {
+                if !scraper.args.continue_on_error {
+                    error!("Timed out while downloading {}: {}", url, e);
+                } else {
+                    warn!("Timed out while downloading {}: {}", url, e);
+                }
+            }
             Err(e) => {
                 if !scraper.args.continue_on_error {
                     error!("Couldn't download a page, {:?}", e);
@@ -281,7 +688,7 @@ impl Scraper {
             }
         }
 
-        scraper.visited_urls.lock().unwrap().insert(url.to_string());
+        scraper.seen.visit(url.as_str());
 
         if scraper.args.verbose {
             if download_filter_matches {
@@ -292,20 +699,18 @@ impl Scraper {
         }
     }
 
-    /// Run through the channel and complete it
+    /// Run through the frontier and complete it
     pub fn run(&mut self) {
-        /* Push the origin URL and depth (0) through the channel */
-        self.map_url_path(
-            &self.args.origin,
-            url_helper::to_path(&self.args.origin, false),
-        );
-        Scraper::push(&self.transmitter, self.args.origin.clone(), 0, 0);
+        /* Push the origin URL and depth (0) into the frontier */
+        self.map_url_path(&self.args.origin, &self.args.origin);
+        self.push(self.args.origin.clone(), dom::LinkKind::Navigation, 0, 0);
+
+        let budget_exhausted = Once::new();
 
         thread::scope(|thread_scope| {
             for _ in 0..self.args.jobs {
-                let tx = self.transmitter.clone();
-                let rx = self.receiver.clone();
                 let self_clone = &self;
+                let budget_exhausted = &budget_exhausted;
 
                 thread_scope.spawn(move |_| {
                     let mut counter = 0;
@@ -313,17 +718,31 @@ impl Scraper {
                     let mut rng = rand::thread_rng();
 
                     while counter < MAX_EMPTY_RECEIVES {
-                        match rx.try_recv() {
-                            Err(e) => match e {
-                                TryRecvError::Empty => {
+                        if let Err(exhausted) = self_clone.budget.check_crawl() {
+                            budget_exhausted.call_once(|| {
+                                info!("{}, stopping the scraping", exhausted);
+                                self_clone.summary.add_exhausted_budget(exhausted);
+                            });
+                            break;
+                        }
+
+                        match self_clone.frontier.pop() {
+                            None => {
+                                // The queued jobs can be waiting for their host to be available
+                                if self_clone.frontier.is_empty() {
                                     counter += 1;
-                                    std::thread::sleep(SLEEP_DURATION);
                                 }
-                                TryRecvError::Disconnected => panic!("{}", e),
-                            },
-                            Ok((url, depth, ext_depth)) => {
+                                std::thread::sleep(SLEEP_DURATION);
+                            }
+                            Some(job) => {
                                 counter = 0;
-                                Scraper::handle_url(self_clone, &tx, url, depth, ext_depth);
+                                Scraper::handle_url(
+                                    self_clone,
+                                    job.url.clone(),
+                                    job.depth,
+                                    job.ext_depth,
+                                );
+                                self_clone.frontier.finish(&job);
                                 self_clone.sleep(&mut rng);
                             }
                         }
@@ -332,6 +751,8 @@ impl Scraper {
             }
         })
         .unwrap();
+
+        self.summary.print();
     }
 
     /// Sleep the thread for a variable amount of seconds to avoid getting banned
@@ -349,12 +770,10 @@ impl Scraper {
         std::thread::sleep(delay_duration);
     }
 
-    /// If a URL should be visited (ignores `mail:`, `javascript:` and other pseudo-links)
-    fn should_visit(scraper: &Scraper, url: &str) -> bool {
-        if scraper.args.exclude_visit.is_match(url) || !scraper.args.include_visit.is_match(url) {
-            return false;
-        }
-        match Url::parse(url) {
+    /// If a URL found in the page at `base` should be visited (ignores `mail:`, `javascript:`
+    /// and other pseudo-links, and the URLs outside of the path prefixes)
+    fn should_visit(scraper: &Scraper, url: &str, kind: dom::LinkKind, base: &Url) -> bool {
+        let is_valid = match Url::parse(url) {
             /* The given candidate is a valid URL, and not a relative path to
              * the next one. Therefore, we have to check if this URL is valid.
              * If it is, we should visit it.
@@ -365,7 +784,26 @@ impl Scraper {
              * path or a malformed URL. If the URL is malformed, then it will
              * be handled during the join() call in run() */
             Err(_) => true,
+        };
+        if !is_valid {
+            return false;
+        }
+
+        // The filters see the same absolute URLs as the download filter in handle_url
+        let full_url = match base.join(&Scraper::normalize_url(url.to_string())) {
+            Ok(full_url) => full_url,
+            Err(_) => return true,
+        };
+        if !scraper.visit_filter.is_match(&full_url) {
+            return false;
+        }
+        if kind == dom::LinkKind::Requisite && scraper.args.requisites_outside_prefix {
+            return true;
         }
+
+        scraper
+            .prefixes
+            .contains(&scraper.canonicalizer.canonicalize(&full_url))
     }
 
     /// Replaces `///` with `//`
@@ -379,25 +817,6 @@ impl Scraper {
         }
         url
     }
-
-    /// If the URL leads to another domain
-    fn is_on_another_domain(url: &str, base: &Url) -> bool {
-        let real_url = Scraper::normalize_url(String::from(url));
-
-        match Url::parse(real_url.as_str()) {
-            /* The given candidate is a valid URL, and not a relative path to
-             * the next one. Therefore, we have to check if this URL belongs
-             * to the same domain as our current URL. If the candidate has the
-             * same domain as our base, and the depth condition is satisfied,
-             * then we should visit it,  */
-            Ok(not_ok) => not_ok.domain() != base.domain(),
-
-            /* Since we couldn't parse this "URL", then it must be a relative
-             * path or a malformed URL. If the URL is malformed, then it will
-             * be handled during the join() call in run() */
-            Err(_) => false,
-        }
-    }
 }
 
 #[cfg(test)]
@@ -407,6 +826,9 @@ mod tests {
     use regex::Regex;
 
     use super::*;
+    use crate::canonical::TrailingSlash;
+    use crate::frontier::Strategy;
+    use crate::scope::Scope;
 
     #[test]
     fn test_zero_delay_range() {
@@ -417,20 +839,75 @@ mod tests {
             tries: 1,
             depth: 5,
             ext_depth: 0,
+            depth_rule: Vec::new(),
             delay: 0,
             user_agent: "suckit".to_string(),
             random_range: 0,
             verbose: true,
-            include_visit: Regex::new(".*").unwrap(),
-            exclude_visit: Regex::new("^$").unwrap(),
-            include_download: Regex::new("jpg").unwrap(),
-            exclude_download: Regex::new("png").unwrap(),
+            include_visit: vec![Regex::new(".*").unwrap()],
+            exclude_visit: vec![Regex::new("^$").unwrap()],
+            include_download: vec![Regex::new("jpg").unwrap()],
+            exclude_download: vec![Regex::new("png").unwrap()],
+            accept: Vec::new(),
+            reject: Vec::new(),
+            visit_rules: Vec::new(),
+            download_rules: Vec::new(),
+            rewrite: Vec::new(),
+            rewrite_keep_paths: false,
             visit_filter_is_download_filter: false,
             auth: Vec::new(),
             continue_on_error: true,
             dry_run: false,
             disable_certs_checks: false,
             cookie: "".to_string(),
+            no_symlinks: false,
+            resolve: Vec::new(),
+            deny_private_networks: false,
+            allow_network: Vec::new(),
+            connect_timeout: None,
+            read_timeout: 30,
+            request_timeout: None,
+            max_duration: None,
+            max_duration_per_host: None,
+            max_pages: None,
+            max_pages_per_host: None,
+            max_bytes: None,
+            max_bytes_per_host: None,
+            abort_in_flight: false,
+            strategy: Strategy::Bfs,
+            priority: Vec::new(),
+            max_jobs_per_host: None,
+            state_dir: None,
+            max_urls_in_memory: None,
+            bloom_filter: None,
+            limit_rate: None,
+            limit_rate_per_host: None,
+            cache_dir: None,
+            offline: false,
+            scope: Scope::Host,
+            no_parent: false,
+            scope_prefix: Vec::new(),
+            requisites_outside_prefix: false,
+            host_alias: Vec::new(),
+            unify_schemes: false,
+            merge_www: false,
+            sort_query: false,
+            drop_param: Vec::new(),
+            drop_tracking_params: false,
+            strip_session_ids: false,
+            trailing_slash: TrailingSlash::Keep,
+            case_insensitive_paths: false,
+            max_url_length: None,
+            max_query_params: None,
+            max_segment_repeats: None,
+            max_urls_per_pattern: None,
+            near_duplicate_distance: None,
+            detect_soft_404s: false,
+            page: false,
+            accept_mime: Vec::new(),
+            reject_mime: Vec::new(),
+            min_size: None,
+            max_size: None,
         };
 
         let _ = Scraper::new(args);
@@ -445,20 +922,75 @@ mod tests {
             tries: 1,
             depth: 5,
             ext_depth: 0,
+            depth_rule: Vec::new(),
             delay: 2,
             user_agent: "suckit".to_string(),
             random_range: 5,
             verbose: true,
-            include_visit: Regex::new(".*").unwrap(),
-            exclude_visit: Regex::new("^$").unwrap(),
-            include_download: Regex::new("jpg").unwrap(),
-            exclude_download: Regex::new("png").unwrap(),
+            include_visit: vec![Regex::new(".*").unwrap()],
+            exclude_visit: vec![Regex::new("^$").unwrap()],
+            include_download: vec![Regex::new("jpg").unwrap()],
+            exclude_download: vec![Regex::new("png").unwrap()],
+            accept: Vec::new(),
+            reject: Vec::new(),
+            visit_rules: Vec::new(),
+            download_rules: Vec::new(),
+            rewrite: Vec::new(),
+            rewrite_keep_paths: false,
             visit_filter_is_download_filter: false,
             auth: Vec::new(),
             continue_on_error: true,
             dry_run: false,
             disable_certs_checks: false,
             cookie: "".to_string(),
+            no_symlinks: false,
+            resolve: Vec::new(),
+            deny_private_networks: false,
+            allow_network: Vec::new(),
+            connect_timeout: None,
+            read_timeout: 30,
+            request_timeout: None,
+            max_duration: None,
+            max_duration_per_host: None,
+            max_pages: None,
+            max_pages_per_host: None,
+            max_bytes: None,
+            max_bytes_per_host: None,
+            abort_in_flight: false,
+            strategy: Strategy::Bfs,
+            priority: Vec::new(),
+            max_jobs_per_host: None,
+            state_dir: None,
+            max_urls_in_memory: None,
+            bloom_filter: None,
+            limit_rate: None,
+            limit_rate_per_host: None,
+            cache_dir: None,
+            offline: false,
+            scope: Scope::Host,
+            no_parent: false,
+            scope_prefix: Vec::new(),
+            requisites_outside_prefix: false,
+            host_alias: Vec::new(),
+            unify_schemes: false,
+            merge_www: false,
+            sort_query: false,
+            drop_param: Vec::new(),
+            drop_tracking_params: false,
+            strip_session_ids: false,
+            trailing_slash: TrailingSlash::Keep,
+            case_insensitive_paths: false,
+            max_url_length: None,
+            max_query_params: None,
+            max_segment_repeats: None,
+            max_urls_per_pattern: None,
+            near_duplicate_distance: None,
+            detect_soft_404s: false,
+            page: false,
+            accept_mime: Vec::new(),
+            reject_mime: Vec::new(),
+            min_size: None,
+            max_size: None,
         };
 
         let _ = Scraper::new(args);
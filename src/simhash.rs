@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::convert::TryInto;
+use std::sync::Mutex;
+
+/// Number of bits of a fingerprint
+const BITS: u32 = 64;
+
+/// Number of consecutive words hashed together as a feature of the text
+const SHINGLE_SIZE: usize = 3;
+
+/// Hash a feature of the text, with a hash stable between runs
+fn hash_feature(feature: &str) -> u64 {
+    let digest = md5::compute(feature.as_bytes());
+    u64::from_le_bytes(digest.0[..8].try_into().unwrap())
+}
+
+/// Compute the SimHash fingerprint of a text, from its shingles of lower cased words.
+/// Similar texts have fingerprints differing by few bits. Returns `None` if the text has no
+/// words, as all empty pages would look the same
+pub fn fingerprint(text: &str) -> Option<u64> {
+    let words: Vec<String> = text
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| word.to_lowercase())
+        .collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut weights = [0i64; BITS as usize];
+    for shingle in words.windows(SHINGLE_SIZE.min(words.len())) {
+        let hash = hash_feature(&shingle.join(" "));
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    }
+
+    Some(
+        weights
+            .iter()
+            .enumerate()
+            .filter(|(_, weight)| **weight > 0)
+            .fold(0, |fingerprint, (bit, _)| fingerprint | (1 << bit)),
+    )
+}
+
+/// Return the number of bits differing between two fingerprints
+pub fn distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// A page whose fingerprint was recorded
+#[derive(Debug, Clone, PartialEq)]
+pub struct Page {
+    pub url: String,
+    pub path: String,
+}
+
+/// The recorded pages, and the indices of the pages having each band
+#[derive(Default)]
+struct Entries {
+    pages: Vec<(u64, Page)>,
+    bands: HashMap<(u32, u64), Vec<usize>>,
+}
+
+/// The fingerprints of the pages seen so far, to find the near-duplicates of new pages.
+/// The fingerprints are split in `threshold + 1` bands: two fingerprints within the threshold
+/// have at least one identical band, so only the pages sharing a band are compared
+pub struct SimHashIndex {
+    threshold: u32,
+    /// The first bit and the width of each band
+    bands: Vec<(u32, u32)>,
+    entries: Mutex<Entries>,
+}
+
+impl SimHashIndex {
+    /// Create an index considering fingerprints within `threshold` bits as near-duplicates
+    pub fn new(threshold: u32) -> SimHashIndex {
+        let threshold = threshold.min(BITS - 1);
+
+        // The bits left over by an even split widen the first bands
+        let count = threshold + 1;
+        let mut start = 0;
+        let bands = (0..count)
+            .map(|band| {
+                let width = BITS / count + u32::from(band < BITS % count);
+                start += width;
+                (start - width, width)
+            })
+            .collect();
+
+        SimHashIndex {
+            threshold,
+            bands,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    /// Return the bands of a fingerprint, with their index
+    fn bands(&self, fingerprint: u64) -> impl Iterator<Item = (u32, u64)> + '_ {
+        self.bands.iter().map(move |&(start, width)| {
+            let mask = u64::MAX >> (BITS - width);
+            (start, (fingerprint >> start) & mask)
+        })
+    }
+
+    /// Return the recorded page near-duplicated by a fingerprint
+    fn find_in(&self, entries: &Entries, fingerprint: u64) -> Option<Page> {
+        for band in self.bands(fingerprint) {
+            if let Some(candidates) = entries.bands.get(&band) {
+                for index in candidates {
+                    let (other, original) = &entries.pages[*index];
+                    if distance(fingerprint, *other) <= self.threshold {
+                        return Some(original.clone());
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Return the page near-duplicated by a new one if any, without recording the new page
+    pub fn find(&self, fingerprint: u64) -> Option<Page> {
+        let entries = self.entries.lock().unwrap();
+        self.find_in(&entries, fingerprint)
+    }
+
+    /// Return the page near-duplicated by a new one if any, or record the new page
+    pub fn find_or_insert(&self, fingerprint: u64, page: Page) -> Option<Page> {
+        let mut entries = self.entries.lock().unwrap();
+
+        if let Some(original) = self.find_in(&entries, fingerprint) {
+            return Some(original);
+        }
+
+        let index = entries.pages.len();
+        entries.pages.push((fingerprint, page));
+        for band in self.bands(fingerprint) {
+            entries.bands.entry(band).or_default().push(index);
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ARTICLE: &str = "SuckIT downloads a website to your disk, following the links of \
+        every page and rewriting them so the copy can be browsed offline. It is fast thanks \
+        to its multiple threads, and it can throttle itself to avoid hammering servers.";
+
+    fn page(name: &str) -> Page {
+        Page {
+            url: format!("https://example.com/{}", name),
+            path: format!("example.com/{}", name),
+        }
+    }
+
+    #[test]
+    fn similar_texts() {
+        let article = fingerprint(ARTICLE).unwrap();
+        let print_view = fingerprint(&format!("Print | {} Share", ARTICLE)).unwrap();
+        let other = fingerprint("A completely different page, about the weather in Paris, which is rainy as usual in autumn").unwrap();
+
+        assert_eq!(fingerprint(&ARTICLE.to_uppercase()), Some(article));
+        assert!(distance(article, print_view) < distance(article, other));
+        assert!(distance(article, other) > 10);
+        assert_eq!(fingerprint(" - "), None);
+    }
+
+    #[test]
+    fn index() {
+        let index = SimHashIndex::new(3);
+
+        assert_eq!(index.find_or_insert(0b1011, page("a")), None);
+        assert_eq!(index.find_or_insert(0b0011, page("b")), Some(page("a")));
+        assert_eq!(index.find_or_insert(u64::MAX, page("c")), None);
+        assert_eq!(
+            index.find_or_insert(u64::MAX << 3, page("d")),
+            Some(page("c"))
+        );
+        assert_eq!(index.find_or_insert(0xff00, page("e")), None);
+
+        assert_eq!(index.find(0b1111), Some(page("a")));
+        assert_eq!(index.find(0xff_0000), None);
+        assert_eq!(index.find_or_insert(0xff_0000, page("f")), None);
+    }
+
+    #[test]
+    fn pigeonhole() {
+        for threshold in 0..BITS {
+            let index = SimHashIndex::new(threshold);
+            assert_eq!(index.bands.len() as u32, threshold + 1);
+            assert_eq!(
+                index.bands.iter().map(|(_, width)| width).sum::<u32>(),
+                BITS
+            );
+        }
+
+        // A bit differs in each byte, so every band of a byte wide split would differ
+        let index = SimHashIndex::new(8);
+        assert_eq!(index.find_or_insert(0, page("a")), None);
+        assert_eq!(
+            index.find_or_insert(0x0101_0101_0101_0101, page("b")),
+            Some(page("a"))
+        );
+    }
+
+    #[test]
+    fn exact_index() {
+        let index = SimHashIndex::new(0);
+
+        assert_eq!(index.find_or_insert(42, page("a")), None);
+        assert_eq!(index.find_or_insert(43, page("b")), None);
+        assert_eq!(index.find_or_insert(42, page("c")), Some(page("a")));
+    }
+}
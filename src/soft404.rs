@@ -0,0 +1,133 @@
+use std::collections::HashMap;
+use std::iter;
+use std::sync::Mutex;
+
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use url::Url;
+
+use super::dom;
+use super::downloader::Downloader;
+use super::response::ResponseData;
+use super::simhash;
+
+/// Hamming distance under which a page has the fingerprint of the not-found page of its host
+const MAX_DISTANCE: u32 = 3;
+
+/// Length of the random path probing the hosts
+const PROBE_LENGTH: usize = 24;
+
+/// Detects the "page not found" templates served with a `200 OK` status, by learning the
+/// not-found page of each host with a request to a random path
+pub struct Soft404Detector {
+    not_found: Mutex<HashMap<String, Option<u64>>>,
+}
+
+impl Soft404Detector {
+    /// Create a detector which has not probed any host yet
+    pub fn new() -> Soft404Detector {
+        Soft404Detector {
+            not_found: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the fingerprint of the text of a page, without the words of its URL which
+    /// not-found pages often repeat
+    fn fingerprint(url: &Url, html: &[u8]) -> Option<u64> {
+        let url_words: Vec<String> = url
+            .path()
+            .split(|c: char| !c.is_alphanumeric())
+            .map(str::to_lowercase)
+            .collect();
+
+        let text = dom::Dom::new(&String::from_utf8_lossy(html)).text();
+        let words: Vec<&str> = text
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|word| !url_words.contains(&word.to_lowercase()))
+            .collect();
+
+        simhash::fingerprint(&words.join(" "))
+    }
+
+    /// Request a random path of the host of `url`, and return the fingerprint of the page if
+    /// the server pretends it exists
+    fn probe(downloader: &Downloader, url: &Url) -> Option<u64> {
+        let random: String = iter::repeat_with(|| rand::thread_rng().sample(Alphanumeric))
+            .map(|c| char::from(c).to_ascii_lowercase())
+            .take(PROBE_LENGTH)
+            .collect();
+        let probe = url.join(&format!("/{}", random)).ok()?;
+
+        match downloader.get(&probe) {
+            Ok(response) if response.status == 200 => match response.data {
+                ResponseData::Html(data) => Soft404Detector::fingerprint(&probe, &data),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    /// Check if a page served with a `200 OK` status is the not-found page of its host,
+    /// probing the host the first time it is seen
+    pub fn is_soft_404(&self, downloader: &Downloader, url: &Url, html: &[u8]) -> bool {
+        // Servers redirecting unknown paths to their home page would make it a soft 404
+        if url.path() == "/" {
+            return false;
+        }
+
+        let host = url.origin().ascii_serialization();
+        let known = self.not_found.lock().unwrap().get(&host).copied();
+        // The probe is a request which can be slow, so the other hosts must not wait for it.
+        // Pages of the same host checked meanwhile probe it too, the first result being kept
+        let not_found = match known {
+            Some(not_found) => not_found,
+            None => {
+                let probed = Soft404Detector::probe(downloader, url);
+                *self.not_found.lock().unwrap().entry(host).or_insert(probed)
+            }
+        };
+
+        match (not_found, Soft404Detector::fingerprint(url, html)) {
+            (Some(not_found), Some(fingerprint)) => {
+                simhash::distance(not_found, fingerprint) <= MAX_DISTANCE
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Default for Soft404Detector {
+    fn default() -> Soft404Detector {
+        Soft404Detector::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fingerprint(url: &str, html: &str) -> Option<u64> {
+        Soft404Detector::fingerprint(&Url::parse(url).unwrap(), html.as_bytes())
+    }
+
+    #[test]
+    fn url_words_ignored() {
+        let template = "<h1>Not found</h1><p>Sorry, the page {} does not exist on this site. \
+            Try the search, or go back to the home page.</p>";
+
+        assert_eq!(
+            fingerprint(
+                "https://example.com/q8vx2kd0",
+                &template.replace("{}", "/q8vx2kd0")
+            ),
+            fingerprint(
+                "https://example.com/blog/old-post.html",
+                &template.replace("{}", "/blog/old-post.html")
+            )
+        );
+        assert_ne!(
+            fingerprint("https://example.com/a", "<p>Some article</p>"),
+            fingerprint("https://example.com/b", &template.replace("{}", "/b"))
+        );
+    }
+}
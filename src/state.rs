@@ -0,0 +1,359 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use sled::{Db, Tree};
+
+use crate::error;
+
+use super::bloom::BloomFilter;
+
+/// Memory used by the database to cache its pages
+const DATABASE_CACHE_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Number of databases opened by the process, naming their directories
+static DATABASES: AtomicUsize = AtomicUsize::new(0);
+
+/// Open a database in `dir` to keep the state of the crawl, which is deleted once the crawl
+/// is over
+pub fn open_database(dir: &Path) -> Result<Db, String> {
+    let number = DATABASES.fetch_add(1, Ordering::Relaxed);
+
+    sled::Config::new()
+        .path(dir.join(format!("suckit-{}-{}", std::process::id(), number)))
+        .temporary(true)
+        .cache_capacity(DATABASE_CACHE_BYTES)
+        .open()
+        .map_err(|e| format!("Couldn't open a database in {}: {}", dir.display(), e))
+}
+
+/// Open a tree of the database
+pub fn open_tree(database: &Db, name: &str) -> Tree {
+    database
+        .open_tree(name)
+        .unwrap_or_else(|e| error!("Couldn't open the {} tree of the database: {}", name, e))
+}
+
+/// Where the seen URLs are kept
+enum Storage {
+    Memory {
+        /// The canonical URLs with their path on the disk
+        paths: HashMap<String, String>,
+        /// The paths of the `paths` map, to find them without a scan
+        used_paths: HashSet<String>,
+        visited: HashSet<String>,
+        /// The paths of the files named by the servers
+        claimed: HashSet<String>,
+    },
+    Disk {
+        paths: Tree,
+        /// The paths of the `paths` tree, to find them without a scan
+        used_paths: Tree,
+        visited: Tree,
+        claimed: Tree,
+        /// Tells without reading the database that most new URLs were not seen
+        bloom: Option<BloomFilter>,
+    },
+}
+
+/// Fail on a database error, which leaves the crawl without its state
+fn check<T>(result: sled::Result<T>) -> T {
+    result.unwrap_or_else(|e| error!("Couldn't use the database: {}", e))
+}
+
+/// Read a string stored in the database
+fn to_string(bytes: sled::IVec) -> String {
+    String::from_utf8_lossy(&bytes).to_string()
+}
+
+impl Storage {
+    /// Create the storage of a database, with a Bloom filter sized for `bloom_capacity` URLs
+    fn disk(database: &Db, bloom_capacity: Option<usize>) -> Storage {
+        Storage::Disk {
+            paths: open_tree(database, "paths"),
+            used_paths: open_tree(database, "used_paths"),
+            visited: open_tree(database, "visited"),
+            claimed: open_tree(database, "claimed"),
+            bloom: bloom_capacity.map(BloomFilter::new),
+        }
+    }
+
+    /// Return the number of URLs
+    fn len(&self) -> usize {
+        match self {
+            Storage::Memory { paths, .. } => paths.len(),
+            Storage::Disk { paths, .. } => paths.len(),
+        }
+    }
+
+    /// Move the URLs kept in memory to a database
+    fn spill(&mut self, database: &Db, bloom_capacity: Option<usize>) {
+        let mut disk = Storage::disk(database, bloom_capacity);
+
+        if let (
+            Storage::Memory {
+                paths,
+                visited,
+                claimed,
+                ..
+            },
+            Storage::Disk {
+                paths: disk_paths,
+                used_paths: disk_used_paths,
+                visited: disk_visited,
+                claimed: disk_claimed,
+                bloom,
+            },
+        ) = (&*self, &mut disk)
+        {
+            for (key, path) in paths {
+                check(disk_paths.insert(key.as_str(), path.as_str()));
+                check(disk_used_paths.insert(path.as_str(), key.as_str()));
+                if let Some(bloom) = bloom {
+                    bloom.insert(key);
+                }
+            }
+            for url in visited {
+                check(disk_visited.insert(url.as_str(), &[]));
+            }
+            for path in claimed {
+                check(disk_claimed.insert(path.as_str(), &[]));
+            }
+        }
+
+        *self = disk;
+    }
+
+    fn path(&self, key: &str) -> Option<String> {
+        match self {
+            Storage::Memory { paths, .. } => paths.get(key).cloned(),
+            Storage::Disk { paths, bloom, .. } => {
+                if bloom.as_ref().is_some_and(|bloom| !bloom.may_contain(key)) {
+                    return None;
+                }
+                check(paths.get(key)).map(to_string)
+            }
+        }
+    }
+
+    fn is_used(&self, path: &str) -> bool {
+        match self {
+            Storage::Memory {
+                used_paths,
+                claimed,
+                ..
+            } => claimed.contains(path) || used_paths.contains(path),
+            Storage::Disk {
+                used_paths,
+                claimed,
+                ..
+            } => check(claimed.contains_key(path)) || check(used_paths.contains_key(path)),
+        }
+    }
+}
+
+/// The URLs seen by the scraper, with the paths of their files. They are kept in memory, or
+/// in a database for very large crawls
+pub struct SeenUrls {
+    storage: Mutex<Storage>,
+    /// The maximum number of URLs kept in memory, the directory of the database taking them
+    /// beyond it, and the capacity of its Bloom filter
+    memory_limit: Option<(usize, PathBuf, Option<usize>)>,
+}
+
+impl SeenUrls {
+    /// Create an empty set in memory
+    pub fn new() -> SeenUrls {
+        SeenUrls {
+            storage: Mutex::new(Storage::Memory {
+                paths: HashMap::new(),
+                used_paths: HashSet::new(),
+                visited: HashSet::new(),
+                claimed: HashSet::new(),
+            }),
+            memory_limit: None,
+        }
+    }
+
+    /// Create an empty set in a database, with a Bloom filter sized for `bloom_capacity` URLs
+    pub fn with_database(database: &Db, bloom_capacity: Option<usize>) -> SeenUrls {
+        SeenUrls {
+            storage: Mutex::new(Storage::disk(database, bloom_capacity)),
+            memory_limit: None,
+        }
+    }
+
+    /// Move the URLs to a database in `dir` once more than `max_urls` are kept in memory, with
+    /// a Bloom filter sized for `bloom_capacity` URLs
+    pub fn with_memory_limit(
+        mut self,
+        max_urls: usize,
+        dir: PathBuf,
+        bloom_capacity: Option<usize>,
+    ) -> SeenUrls {
+        self.memory_limit = Some((max_urls, dir, bloom_capacity));
+        self
+    }
+
+    /// Add a canonical URL with the path of its file, and return if it was not seen yet
+    pub fn insert(&self, key: &str, path: &str) -> bool {
+        let mut storage = self.storage.lock().unwrap();
+        if storage.path(key).is_some() {
+            return false;
+        }
+
+        match &mut *storage {
+            Storage::Memory {
+                paths, used_paths, ..
+            } => {
+                paths.insert(key.to_string(), path.to_string());
+                used_paths.insert(path.to_string());
+            }
+            Storage::Disk {
+                paths,
+                used_paths,
+                bloom,
+                ..
+            } => {
+                check(paths.insert(key, path));
+                check(used_paths.insert(path, key));
+                if let Some(bloom) = bloom {
+                    bloom.insert(key);
+                }
+            }
+        }
+
+        if let Some((max_urls, dir, bloom_capacity)) = &self.memory_limit {
+            if matches!(*storage, Storage::Memory { .. }) && storage.len() > *max_urls {
+                let database = open_database(dir).unwrap_or_else(|e| error!("{}", e));
+                storage.spill(&database, *bloom_capacity);
+            }
+        }
+        true
+    }
+
+    /// Return the path of the file of a canonical URL
+    pub fn path(&self, key: &str) -> Option<String> {
+        self.storage.lock().unwrap().path(key)
+    }
+
+    /// Record that an URL was visited
+    pub fn visit(&self, url: &str) {
+        match &mut *self.storage.lock().unwrap() {
+            Storage::Memory { visited, .. } => {
+                visited.insert(url.to_string());
+            }
+            Storage::Disk { visited, .. } => {
+                check(visited.insert(url, &[]));
+            }
+        }
+    }
+
+    /// Reserve the first path given by `candidate` for a counter starting at 0 which is not
+    /// used by another file, `path` being the one of the URL
+    pub fn claim_path<F>(&self, path: &str, mut candidate: F) -> String
+    where
+        F: FnMut(usize) -> String,
+    {
+        let mut storage = self.storage.lock().unwrap();
+
+        let mut counter = 0;
+        let mut claimed_path = candidate(counter);
+        while claimed_path != path && storage.is_used(&claimed_path) {
+            counter += 1;
+            claimed_path = candidate(counter);
+        }
+
+        match &mut *storage {
+            Storage::Memory { claimed, .. } => {
+                claimed.insert(claimed_path.clone());
+            }
+            Storage::Disk { claimed, .. } => {
+                check(claimed.insert(claimed_path.as_str(), &[]));
+            }
+        }
+        claimed_path
+    }
+}
+
+impl Default for SeenUrls {
+    fn default() -> SeenUrls {
+        SeenUrls::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check_semantics(seen: &SeenUrls) {
+        assert!(seen.insert("https://a.com/", "a.com/index.html"));
+        assert!(!seen.insert("https://a.com/", "a.com/other.html"));
+        assert_eq!(
+            seen.path("https://a.com/"),
+            Some("a.com/index.html".to_string())
+        );
+        assert_eq!(seen.path("https://a.com/b"), None);
+
+        let claim = |path: &str| {
+            seen.claim_path(path, |counter| match counter {
+                0 => "a.com/index.html".to_string(),
+                n => format!("a.com/index_{}.html", n),
+            })
+        };
+        assert_eq!(claim("a.com/index.html"), "a.com/index.html");
+        assert_eq!(claim("a.com/download"), "a.com/index_1.html");
+        assert_eq!(claim("a.com/download"), "a.com/index_2.html");
+
+        seen.visit("https://a.com/");
+    }
+
+    #[test]
+    fn memory() {
+        check_semantics(&SeenUrls::new());
+    }
+
+    #[test]
+    fn memory_limit() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+        let seen = SeenUrls::new().with_memory_limit(1, dir.to_path_buf(), Some(100));
+
+        assert!(seen.insert("https://a.com/x", "a.com/x.html"));
+        seen.visit("https://a.com/x");
+        seen.claim_path("a.com/x.html", |_| "a.com/c.pdf".to_string());
+        assert!(matches!(
+            *seen.storage.lock().unwrap(),
+            Storage::Memory { .. }
+        ));
+        check_semantics(&seen);
+        assert!(matches!(
+            *seen.storage.lock().unwrap(),
+            Storage::Disk { .. }
+        ));
+
+        // The URLs seen before are kept
+        assert!(!seen.insert("https://a.com/x", "a.com/other.html"));
+        assert_eq!(
+            seen.path("https://a.com/x"),
+            Some("a.com/x.html".to_string())
+        );
+        assert_eq!(
+            seen.claim_path("a.com/d.pdf", |counter| match counter {
+                0 => "a.com/c.pdf".to_string(),
+                n => format!("a.com/c_{}.pdf", n),
+            }),
+            "a.com/c_1.pdf"
+        );
+    }
+
+    #[test]
+    fn database() {
+        let dir = mktemp::Temp::new_dir().unwrap();
+
+        let database = open_database(&dir).unwrap();
+        check_semantics(&SeenUrls::with_database(&database, None));
+        let database = open_database(&dir).unwrap();
+        check_semantics(&SeenUrls::with_database(&database, Some(100)));
+    }
+}
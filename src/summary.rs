@@ -0,0 +1,151 @@
+use std::sync::Mutex;
+
+use url::Url;
+
+use crate::info;
+
+use super::budget::Exhausted;
+use super::trap::TrapReason;
+
+/// Maximum number of URLs listed for each kind of event, the others being only counted
+const MAX_LISTED_URLS: usize = 100;
+
+/// The first events of a kind, with the number of events
+struct Events<T> {
+    listed: Vec<T>,
+    count: usize,
+}
+
+impl<T> Events<T> {
+    fn new() -> Events<T> {
+        Events {
+            listed: Vec::new(),
+            count: 0,
+        }
+    }
+
+    /// Record an event, only kept if the list is not full so the memory stays bounded
+    fn push(&mut self, event: T) {
+        if self.listed.len() < MAX_LISTED_URLS {
+            self.listed.push(event);
+        }
+        self.count += 1;
+    }
+
+    /// Print the listed events, and how many were left out
+    fn print<F>(&self, format: F)
+    where
+        F: Fn(&T) -> String,
+    {
+        for event in self.listed.iter() {
+            info!("  {}", format(event));
+        }
+        if self.count > self.listed.len() {
+            info!("  ... and {} more", self.count - self.listed.len());
+        }
+    }
+}
+
+/// What happened during the scraping, reported once it is over
+pub struct Summary {
+    traps: Mutex<Events<(String, TrapReason)>>,
+    near_duplicates: Mutex<Events<(String, String)>>,
+    broken_links: Mutex<Events<(String, String)>>,
+    exhausted_budgets: Mutex<Vec<Exhausted>>,
+}
+
+impl Summary {
+    /// Create an empty summary
+    pub fn new() -> Summary {
+        Summary {
+            traps: Mutex::new(Events::new()),
+            near_duplicates: Mutex::new(Events::new()),
+            broken_links: Mutex::new(Events::new()),
+            exhausted_budgets: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Record an URL skipped because it looks like a crawler trap
+    pub fn add_trap(&self, url: &Url, reason: TrapReason) {
+        self.traps.lock().unwrap().push((url.to_string(), reason));
+    }
+
+    /// Record a page saved as a link to the page it nearly duplicates
+    pub fn add_near_duplicate(&self, url: &Url, original: &str) {
+        self.near_duplicates
+            .lock()
+            .unwrap()
+            .push((url.to_string(), original.to_string()));
+    }
+
+    /// Record an URL leading to a missing page
+    pub fn add_broken_link(&self, url: &Url, reason: &str) {
+        self.broken_links
+            .lock()
+            .unwrap()
+            .push((url.to_string(), reason.to_string()));
+    }
+
+    /// Record a budget which stopped the downloads, once
+    pub fn add_exhausted_budget(&self, exhausted: Exhausted) {
+        let mut exhausted_budgets = self.exhausted_budgets.lock().unwrap();
+        if !exhausted_budgets.contains(&exhausted) {
+            exhausted_budgets.push(exhausted);
+        }
+    }
+
+    /// Print the summary, if there is anything to report
+    pub fn print(&self) {
+        let traps = self.traps.lock().unwrap();
+        if traps.count > 0 {
+            info!("Skipped {} URLs looking like crawler traps:", traps.count);
+            traps.print(|(url, reason)| format!("{}: {}", url, reason));
+        }
+
+        let near_duplicates = self.near_duplicates.lock().unwrap();
+        if near_duplicates.count > 0 {
+            info!(
+                "Saved {} near-duplicate pages as links:",
+                near_duplicates.count
+            );
+            near_duplicates.print(|(url, original)| format!("{} -> {}", url, original));
+        }
+
+        let broken_links = self.broken_links.lock().unwrap();
+        if broken_links.count > 0 {
+            info!("Found {} broken links:", broken_links.count);
+            broken_links.print(|(url, reason)| format!("{}: {}", url, reason));
+        }
+
+        let exhausted_budgets = self.exhausted_budgets.lock().unwrap();
+        if !exhausted_budgets.is_empty() {
+            info!("Stopped downloading as budgets were exhausted:");
+            for exhausted in exhausted_budgets.iter() {
+                info!("  {}", exhausted);
+            }
+        }
+    }
+}
+
+impl Default for Summary {
+    fn default() -> Summary {
+        Summary::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded_events() {
+        let mut events = Events::new();
+        for event in 0..MAX_LISTED_URLS + 5 {
+            events.push(event);
+        }
+
+        assert_eq!(events.count, MAX_LISTED_URLS + 5);
+        assert_eq!(events.listed.len(), MAX_LISTED_URLS);
+        assert_eq!(events.listed.last(), Some(&(MAX_LISTED_URLS - 1)));
+    }
+}
@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+use lazy_static::lazy_static;
+use regex::Regex;
+use url::Url;
+
+use super::args;
+
+/// Why an URL was considered a crawler trap
+#[derive(Debug, Clone, PartialEq)]
+pub enum TrapReason {
+    /// A path segment is repeated, like in `/a/b/a/b/a/b`
+    RepeatedSegment(String),
+    /// The URL is longer than the limit
+    TooLong(usize),
+    /// The query has more parameters than the limit
+    TooManyParams(usize),
+    /// Too many URLs share the same pattern, like the pages of a calendar
+    TooManyUnderPattern(String),
+}
+
+impl fmt::Display for TrapReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrapReason::RepeatedSegment(segment) => {
+                write!(f, "the path segment \"{}\" is repeated", segment)
+            }
+            TrapReason::TooLong(length) => write!(f, "the URL is {} characters long", length),
+            TrapReason::TooManyParams(count) => {
+                write!(f, "the query has {} parameters", count)
+            }
+            TrapReason::TooManyUnderPattern(pattern) => {
+                write!(f, "too many URLs match the pattern {}", pattern)
+            }
+        }
+    }
+}
+
+/// Detects the URLs which would make the scraping endless, like calendars, faceted search
+/// and buggy relative links. Only the checks given a limit are made
+pub struct TrapDetector {
+    max_url_length: Option<usize>,
+    max_query_params: Option<usize>,
+    max_segment_repeats: Option<usize>,
+    max_urls_per_pattern: Option<usize>,
+    pattern_counts: Mutex<HashMap<String, usize>>,
+}
+
+impl TrapDetector {
+    /// Create a detector with the limits given on the command line
+    pub fn new(args: &args::Args) -> TrapDetector {
+        TrapDetector {
+            max_url_length: args.max_url_length,
+            max_query_params: args.max_query_params,
+            max_segment_repeats: args.max_segment_repeats,
+            max_urls_per_pattern: args.max_urls_per_pattern,
+            pattern_counts: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Return the pattern of an URL: its numbers are replaced, and only the names of its
+    /// query parameters are kept
+    fn pattern(url: &Url) -> String {
+        lazy_static! {
+            static ref NUMBER_REGEX: Regex = Regex::new(r"[0-9]+").unwrap();
+        }
+
+        let mut params: Vec<String> = url
+            .query_pairs()
+            .map(|(name, _)| name.into_owned())
+            .collect();
+        params.sort_unstable();
+        params.dedup();
+
+        let path = NUMBER_REGEX.replace_all(url.path(), "N");
+        let mut pattern = format!("{}{}", url.host_str().unwrap_or(""), path);
+        if !params.is_empty() {
+            pattern.push('?');
+            pattern.push_str(&params.join("&"));
+        }
+
+        pattern
+    }
+
+    /// Return the first path segment appearing too many times, if any
+    fn repeated_segment(url: &Url, max_segment_repeats: usize) -> Option<String> {
+        let mut counts: HashMap<&str, usize> = HashMap::new();
+
+        for segment in url.path_segments()?.filter(|segment| !segment.is_empty()) {
+            let count = counts.entry(segment).or_default();
+            *count += 1;
+            if *count > max_segment_repeats {
+                return Some(segment.to_string());
+            }
+        }
+
+        None
+    }
+
+    /// Check if a newly found URL is a trap. URLs which are not are counted in their pattern
+    pub fn check(&self, url: &Url) -> Result<(), TrapReason> {
+        if let Some(max_url_length) = self.max_url_length {
+            let length = url.as_str().len();
+            if length > max_url_length {
+                return Err(TrapReason::TooLong(length));
+            }
+        }
+
+        if let Some(max_query_params) = self.max_query_params {
+            let params = url.query_pairs().count();
+            if params > max_query_params {
+                return Err(TrapReason::TooManyParams(params));
+            }
+        }
+
+        if let Some(max_segment_repeats) = self.max_segment_repeats {
+            if let Some(segment) = TrapDetector::repeated_segment(url, max_segment_repeats) {
+                return Err(TrapReason::RepeatedSegment(segment));
+            }
+        }
+
+        if let Some(max_urls_per_pattern) = self.max_urls_per_pattern {
+            let pattern = TrapDetector::pattern(url);
+            let mut pattern_counts = self.pattern_counts.lock().unwrap();
+            let count = pattern_counts.entry(pattern.clone()).or_default();
+            if *count >= max_urls_per_pattern {
+                return Err(TrapReason::TooManyUnderPattern(pattern));
+            }
+            *count += 1;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use structopt::StructOpt;
+
+    use super::*;
+
+    fn detector(flags: &[&str]) -> TrapDetector {
+        let mut args = vec!["suckit", "https://example.com"];
+        args.extend_from_slice(flags);
+
+        TrapDetector::new(&args::Args::from_iter(args))
+    }
+
+    fn check(detector: &TrapDetector, url: &str) -> Result<(), TrapReason> {
+        detector.check(&Url::parse(url).unwrap())
+    }
+
+    #[test]
+    fn repeated_segments() {
+        let detector = detector(&["--max-segment-repeats", "2"]);
+
+        assert_eq!(check(&detector, "https://example.com/a/b/a/b/"), Ok(()));
+        assert_eq!(
+            check(&detector, "https://example.com/a/b/a/b/a/b/"),
+            Err(TrapReason::RepeatedSegment("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn url_length() {
+        let detector = detector(&["--max-url-length", "30"]);
+
+        assert_eq!(check(&detector, "https://example.com/short"), Ok(()));
+        assert_eq!(
+            check(&detector, "https://example.com/a-little-too-long"),
+            Err(TrapReason::TooLong(37))
+        );
+    }
+
+    #[test]
+    fn query_params() {
+        let detector = detector(&["--max-query-params", "2"]);
+
+        assert_eq!(check(&detector, "https://example.com/?a=1&b=2"), Ok(()));
+        assert_eq!(
+            check(&detector, "https://example.com/?a=1&b=2&c=3"),
+            Err(TrapReason::TooManyParams(3))
+        );
+    }
+
+    #[test]
+    fn urls_per_pattern() {
+        let detector = detector(&["--max-urls-per-pattern", "2"]);
+
+        assert_eq!(
+            check(&detector, "https://example.com/calendar/2024/05?view=day"),
+            Ok(())
+        );
+        assert_eq!(
+            check(&detector, "https://example.com/calendar/2024/06?view=week"),
+            Ok(())
+        );
+        assert_eq!(
+            check(&detector, "https://example.com/calendar/2024/07?view=day"),
+            Err(TrapReason::TooManyUnderPattern(
+                "example.com/calendar/N/N?view".to_string()
+            ))
+        );
+        assert_eq!(check(&detector, "https://example.com/calendar/"), Ok(()));
+    }
+
+    #[test]
+    fn disabled_checks() {
+        let detector = detector(&[]);
+
+        assert_eq!(
+            check(
+                &detector,
+                &format!(
+                    "https://example.com/{}?{}",
+                    "a/".repeat(2000),
+                    "a=1&".repeat(20)
+                )
+            ),
+            Ok(())
+        );
+    }
+}
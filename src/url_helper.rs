@@ -4,7 +4,7 @@ use md5;
 use url::Url;
 
 ///Max file name size supported by the file system
-const FILE_NAME_MAX_LENGTH: usize = 255;
+pub const FILE_NAME_MAX_LENGTH: usize = 255;
 
 /// Convert an Url to the corresponding path
 pub fn to_path(url: &Url, with_fragment: bool) -> String {
@@ -6,8 +6,8 @@ use std::fs::read_dir;
 use std::process::Command;
 use std::process::Stdio;
 
-const PAGE: &'static str = "tests/fixtures/";
-const IP: &'static str = "0.0.0.0";
+const PAGE: &str = "tests/fixtures/";
+const IP: &str = "0.0.0.0";
 
 // Shouldn't supply credentials to a non-matching host
 #[test]
@@ -19,7 +19,7 @@ fn auth_different_host() {
     let output_dir = tempdir.to_str().unwrap();
 
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[
+        .args([
             &url,
             "-o",
             output_dir,
@@ -49,7 +49,7 @@ fn auth_valid() {
     let output_dir = tempdir.to_str().unwrap();
 
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir, "-a", "username password"])
+        .args([&url, "-o", output_dir, "-a", "username password"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -0,0 +1,124 @@
+//! Tests for the crawl budgets
+
+mod fixtures;
+
+use std::fs::read_dir;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+// The home page links to 5 pages of its host and 5 pages of other.test
+fn scrape(extra_args: &[&str]) -> (mktemp::Temp, String) {
+    let ip = fixtures::spawn_linking_http_server(|port, path| match path {
+        "/" => (1..=5)
+            .map(|page| {
+                format!(
+                    "<a href=\"/a{}.html\">A</a><a href=\"http://other.test:{}/b{}.html\">B</a>",
+                    page, port, page
+                )
+            })
+            .collect(),
+        _ => "Leaf".to_string(),
+    });
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "--ext-depth", "1"])
+        .args(["--resolve", "other.test:*:127.0.0.1"])
+        .args(extra_args)
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    (tempdir, String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn count_files(dir: &Path) -> usize {
+    read_dir(dir).map_or(0, |entries| entries.count())
+}
+
+#[test]
+fn max_pages() {
+    let (tempdir, stdout) = scrape(&["--max-pages", "4", "-j", "4"]);
+    let output_dir = Path::new(tempdir.to_str().unwrap());
+
+    assert_eq!(
+        count_files(&output_dir.join("0.0.0.0")) + count_files(&output_dir.join("other.test")),
+        4
+    );
+    assert!(stdout.contains("Stopped downloading as budgets were exhausted:"));
+    assert!(stdout.contains("  Maximum number of pages reached (4)"));
+}
+
+#[test]
+fn max_pages_per_host() {
+    let (tempdir, stdout) = scrape(&["--max-pages-per-host", "3"]);
+    let output_dir = Path::new(tempdir.to_str().unwrap());
+
+    assert_eq!(count_files(&output_dir.join("0.0.0.0")), 3);
+    assert_eq!(count_files(&output_dir.join("other.test")), 3);
+    assert!(stdout.contains("  Maximum number of pages reached (3) for 0.0.0.0"));
+    assert!(stdout.contains("  Maximum number of pages reached (3) for other.test"));
+}
+
+#[test]
+fn max_bytes() {
+    let (tempdir, stdout) = scrape(&["--max-bytes", "1", "-j", "1"]);
+    let output_dir = Path::new(tempdir.to_str().unwrap());
+
+    // The home page exhausts the budget
+    assert!(output_dir.join("0.0.0.0/index.html").is_file());
+    assert_eq!(count_files(&output_dir.join("0.0.0.0")), 1);
+    assert!(!output_dir.join("other.test").exists());
+    assert!(stdout.contains("Maximum number of bytes reached (1), stopping the scraping"));
+}
+
+// index.html is 466 bytes long, at 20 bytes per second its download takes more than 20 seconds
+#[test]
+fn abort_in_flight() {
+    let ip = fixtures::spawn_local_http_server("tests/fixtures/", false, None);
+    let url = format!("http://{}", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let start = Instant::now();
+    let output = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "--limit-rate", "20"])
+        .args(["--max-duration", "1", "--abort-in-flight"])
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    assert!(start.elapsed() < Duration::from_secs(10));
+    assert!(!Path::new(output_dir).join("0.0.0.0").exists());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("  Maximum duration reached (1s)"));
+}
+
+// index.html is 466 bytes long, so its download exceeds the budget on its own
+#[test]
+fn abort_large_download() {
+    let ip = fixtures::spawn_local_http_server("tests/fixtures/", false, None);
+    let url = format!("http://{}", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir])
+        .args(["--max-bytes", "100", "--abort-in-flight"])
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    assert!(!Path::new(output_dir).join("0.0.0.0").exists());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("  Maximum number of bytes reached (100)"));
+}
@@ -0,0 +1,92 @@
+//! Tests for the --cache-dir and --offline flags
+
+mod fixtures;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::atomic::Ordering;
+
+const PAGE: &str = "tests/fixtures/";
+const IP: &str = "0.0.0.0";
+const FRESH_HEADERS: [(&str, &str); 1] = [("Cache-Control", "max-age=3600")];
+const UNCACHED_HEADERS: [(&str, &str); 1] = [("Cache-Control", "no-store")];
+
+fn run_suckit(url: &str, output_dir: &str, extra_args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([url, "-o", output_dir])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+}
+
+// Fresh responses are served by the cache without touching the network
+#[test]
+fn fresh_responses_are_reused() {
+    let (ip, count) = fixtures::spawn_counting_http_server(PAGE, &FRESH_HEADERS);
+    let url = format!("http://{}", ip);
+    let cache_dir = mktemp::Temp::new_dir().unwrap();
+    let cache_dir = cache_dir.to_str().unwrap();
+
+    for _ in 0..2 {
+        let tempdir = mktemp::Temp::new_dir().unwrap();
+        let output_dir = tempdir.to_str().unwrap();
+
+        run_suckit(&url, output_dir, &["-d", "0", "--cache-dir", cache_dir]);
+        assert!(Path::new(output_dir).join(IP).join("index.html").is_file());
+    }
+
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
+
+// no-store responses are never cached
+#[test]
+fn no_store_responses_are_downloaded() {
+    let (ip, count) = fixtures::spawn_counting_http_server(PAGE, &UNCACHED_HEADERS);
+    let url = format!("http://{}", ip);
+    let cache_dir = mktemp::Temp::new_dir().unwrap();
+    let cache_dir = cache_dir.to_str().unwrap();
+
+    for _ in 0..2 {
+        let tempdir = mktemp::Temp::new_dir().unwrap();
+        run_suckit(
+            &url,
+            tempdir.to_str().unwrap(),
+            &["-d", "0", "--cache-dir", cache_dir],
+        );
+    }
+
+    assert_eq!(count.load(Ordering::SeqCst), 2);
+}
+
+// The offline mode serves stale responses, and skips the ones missing from the cache
+#[test]
+fn offline() {
+    let (ip, count) = fixtures::spawn_counting_http_server(PAGE, &[]);
+    let url = format!("http://{}", ip);
+    let cache_dir = mktemp::Temp::new_dir().unwrap();
+    let cache_dir = cache_dir.to_str().unwrap();
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    run_suckit(
+        &url,
+        tempdir.to_str().unwrap(),
+        &["-d", "0", "--cache-dir", cache_dir],
+    );
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+    run_suckit(
+        &url,
+        output_dir,
+        &["--cache-dir", cache_dir, "--offline", "-d", "1"],
+    );
+
+    let files_dir = Path::new(output_dir).join(IP);
+    assert!(files_dir.join("index.html").is_file());
+    assert!(!files_dir.join("file.txt").exists());
+    assert_eq!(count.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,129 @@
+//! Tests for the URL canonicalization flags, like --merge-www and --sort-query
+
+mod fixtures;
+
+use std::fs::read_to_string;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const BARE_HOST: &str = "suckit.test";
+const WWW_HOST: &str = "www.suckit.test";
+const ALIAS_HOST: &str = "mirror.test";
+
+// The start page links to the same page through the bare, www and aliased hosts
+fn alias_links(port: u16) -> String {
+    [BARE_HOST, WWW_HOST, ALIAS_HOST]
+        .iter()
+        .map(|host| format!("<a href=\"http://{}:{}/page.html\">page</a>", host, port))
+        .collect()
+}
+
+// The start page links to the same pages through equivalent URLs
+fn equivalent_links(_: u16) -> String {
+    concat!(
+        "<a href=\"/a?y=2&x=1\">a</a>",
+        "<a href=\"/a?x=1&y=2&utm_source=feed\">a</a>",
+        "<a href=\"/Kernel\">Kernel</a>",
+        "<a href=\"/Kernel/\">Kernel</a>",
+        "<a href=\"/Kernel/#contents\">Kernel</a>",
+    )
+    .to_string()
+}
+
+fn scrape(start_page: fn(u16) -> String, extra_args: &[&str]) -> mktemp::Temp {
+    let ip = fixtures::spawn_linking_http_server(move |port, path| match path {
+        "/" => start_page(port),
+        _ => String::new(),
+    });
+    let port = ip.rsplit(':').next().unwrap();
+    let url = format!("http://{}:{}/", BARE_HOST, port);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let mut args = vec![url, "-o".to_string(), output_dir.to_string()];
+    for host in [BARE_HOST, WWW_HOST, ALIAS_HOST] {
+        args.push("--resolve".to_string());
+        args.push(format!("{}:*:127.0.0.1", host));
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args(&args)
+        .args(["--ext-depth", "1"])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tempdir
+}
+
+fn exists(output_dir: &mktemp::Temp, path: &str) -> bool {
+    Path::new(output_dir.as_path())
+        .join(BARE_HOST)
+        .join(path)
+        .exists()
+}
+
+#[test]
+fn separate_hosts() {
+    let output_dir = scrape(alias_links, &[]);
+    let output_dir = Path::new(output_dir.as_path());
+
+    for host in [BARE_HOST, WWW_HOST, ALIAS_HOST] {
+        assert!(output_dir.join(host).join("page.html").is_file());
+    }
+}
+
+#[test]
+fn merged_hosts() {
+    let alias = format!("{}={}", ALIAS_HOST, BARE_HOST);
+    let output_dir = scrape(alias_links, &["--merge-www", "--host-alias", &alias]);
+    let output_dir = Path::new(output_dir.as_path());
+
+    assert!(output_dir.join(BARE_HOST).join("page.html").is_file());
+    assert!(!output_dir.join(WWW_HOST).exists());
+    assert!(!output_dir.join(ALIAS_HOST).exists());
+
+    // Every link points to the single copy
+    let index = read_to_string(output_dir.join(BARE_HOST).join("index.html")).unwrap();
+    assert_eq!(index.matches("href=\"page.html\"").count(), 3);
+}
+
+#[test]
+fn without_canonicalization() {
+    let output_dir = scrape(equivalent_links, &[]);
+
+    assert!(exists(&output_dir, "a__querystring__y=2&x=1"));
+    assert!(exists(
+        &output_dir,
+        "a__querystring__x=1&y=2&utm_source=feed"
+    ));
+    assert!(exists(&output_dir, "Kernel/index_no_slash.html"));
+    assert!(exists(&output_dir, "Kernel/index.html"));
+}
+
+#[test]
+fn with_canonicalization() {
+    let output_dir = scrape(
+        equivalent_links,
+        &[
+            "--sort-query",
+            "--drop-tracking-params",
+            "--trailing-slash",
+            "add",
+        ],
+    );
+
+    // The path of the pages gets a trailing slash too
+    assert!(exists(&output_dir, "a/__querystring__x=1&y=2"));
+    assert!(!exists(&output_dir, "a/__querystring__y=2&x=1"));
+    assert!(!exists(
+        &output_dir,
+        "a/__querystring__x=1&y=2&utm_source=feed"
+    ));
+    assert!(exists(&output_dir, "Kernel/index.html"));
+    assert!(!exists(&output_dir, "Kernel/index_no_slash.html"));
+}
@@ -4,11 +4,10 @@ mod fixtures;
 
 use std::fs;
 use std::process::{Command, Stdio};
-use std::sync::Once;
 
-const PAGE: &'static str = "tests/fixtures";
-const PAGE_META: &'static str = "tests/fixtures/charset_test_html.html";
-const IP: &'static str = "0.0.0.0";
+const PAGE: &str = "tests/fixtures";
+const PAGE_META: &str = "tests/fixtures/charset_test_html.html";
+const IP: &str = "0.0.0.0";
 
 #[test]
 fn test_html_charset_found() {
@@ -20,7 +19,7 @@ fn test_html_charset_found() {
     let file_dir = format!("{}/{}", output_dir, IP);
     let url = format!("http://{}/charset_test_html.html", addr);
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir])
+        .args([&url, "-o", output_dir])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -5,9 +5,9 @@ mod fixtures;
 use std::fs;
 use std::process::{Command, Stdio};
 
-const PAGE: &'static str = "tests/fixtures/";
-const PAGE_NO_META: &'static str = "tests/fixtures/charset_test_html_no_meta.html";
-const IP: &'static str = "0.0.0.0";
+const PAGE: &str = "tests/fixtures/";
+const PAGE_NO_META: &str = "tests/fixtures/charset_test_html_no_meta.html";
+const IP: &str = "0.0.0.0";
 
 #[test]
 fn test_html_charset_not_found() {
@@ -20,7 +20,7 @@ fn test_html_charset_not_found() {
     // Spawn a single instance of a local http server usable by all tests in this module.
     let file_dir = format!("{}/{}", output_dir, IP);
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir])
+        .args([&url, "-o", output_dir])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
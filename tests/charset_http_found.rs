@@ -7,9 +7,9 @@ use std::process::{Command, Stdio};
 
 use lazy_static::lazy_static;
 
-const PAGE: &'static str = "tests/fixtures/";
-const PAGE_NO_META: &'static str = "tests/fixtures/charset_test_html_no_meta.html";
-const IP: &'static str = "0.0.0.0";
+const PAGE: &str = "tests/fixtures/";
+const PAGE_NO_META: &str = "tests/fixtures/charset_test_html_no_meta.html";
+const IP: &str = "0.0.0.0";
 
 lazy_static! {
     static ref CHARSET_HEADER: Vec<(&'static str, &'static str)> =
@@ -26,7 +26,7 @@ fn test_http_charset_found() {
 
     let file_dir = format!("{}/{}", output_dir, IP);
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir])
+        .args([&url, "-o", output_dir])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
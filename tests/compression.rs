@@ -0,0 +1,59 @@
+//! Tests for the handling of Content-Encoding
+
+mod fixtures;
+
+use std::fs;
+use std::process::{Command, Stdio};
+
+use lazy_static::lazy_static;
+
+const PAGE: &str = "tests/fixtures/";
+const IP: &str = "0.0.0.0";
+
+lazy_static! {
+    static ref GZIP_ARCHIVE_HEADERS: Vec<(&'static str, &'static str)> =
+        vec![("Content-Type", "text/plain"), ("Content-Encoding", "gzip"),];
+    static ref GZIP_PAGE_HEADERS: Vec<(&'static str, &'static str)> =
+        vec![("Content-Type", "text/html"), ("Content-Encoding", "gzip"),];
+}
+
+fn run_suckit(url: &str, output_dir: &str) {
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([url, "-o", output_dir, "-d", "0"])
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .unwrap();
+    let status = cmd.wait().unwrap();
+    assert!(status.success());
+}
+
+// A .gz file must be stored compressed, even if the server says it is encoded
+#[test]
+fn precompressed_file_is_kept() {
+    let ip = fixtures::spawn_local_http_server(PAGE, false, Some(&GZIP_ARCHIVE_HEADERS));
+    let url = format!("http://{}/archive.txt.gz", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+    run_suckit(&url, output_dir);
+
+    let data_source = fs::read(format!("{}archive.txt.gz", PAGE)).unwrap();
+    let data_downloaded = fs::read(format!("{}/{}/archive.txt.gz", output_dir, IP)).unwrap();
+
+    assert!(fixtures::do_vecs_match(&data_source, &data_downloaded));
+}
+
+// A compressed page must be decoded so its links can be followed
+#[test]
+fn compressed_page_is_decoded() {
+    let ip = fixtures::spawn_local_http_server(PAGE, false, Some(&GZIP_PAGE_HEADERS));
+    let url = format!("http://{}/gzipped.html", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+    run_suckit(&url, output_dir);
+
+    let page = fs::read_to_string(format!("{}/{}/gzipped.html", output_dir, IP)).unwrap();
+    assert!(page.contains("<a href=\"file.txt\">"));
+}
@@ -0,0 +1,67 @@
+//! Tests for files named by a Content-Disposition header
+
+mod fixtures;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+use lazy_static::lazy_static;
+
+const PAGE: &str = "tests/fixtures/";
+const IP: &str = "0.0.0.0";
+
+lazy_static! {
+    static ref DISPOSITION_HEADERS: Vec<(&'static str, &'static str)> = vec![
+        ("Content-Type", "text/plain"),
+        (
+            "Content-Disposition",
+            r#"attachment; filename="../../evil.txt""#
+        ),
+    ];
+}
+
+fn run_suckit(extra_args: &[&str]) -> mktemp::Temp {
+    let ip = fixtures::spawn_local_http_server(PAGE, false, Some(&DISPOSITION_HEADERS));
+    let url = format!("http://{}/file.txt", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap().to_string();
+    let output = Path::new(&output_dir).join("output");
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output.to_str().unwrap()])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .unwrap();
+    let status = cmd.wait().unwrap();
+    assert!(status.success());
+
+    tempdir
+}
+
+// The suggested name must stay in the host directory
+#[test]
+fn disposition_path_traversal() {
+    let tempdir = run_suckit(&[]);
+    let output = tempdir.join("output");
+
+    assert!(output.join(IP).join("evil.txt").is_file());
+    assert!(!tempdir.join("evil.txt").exists());
+    assert!(!output.join("evil.txt").exists());
+
+    let link = fs::symlink_metadata(output.join(IP).join("file.txt")).unwrap();
+    assert!(link.file_type().is_symlink());
+}
+
+#[test]
+fn disposition_no_symlinks() {
+    let tempdir = run_suckit(&["--no-symlinks"]);
+    let output = tempdir.join("output");
+
+    let copy = fs::symlink_metadata(output.join(IP).join("file.txt")).unwrap();
+    assert!(copy.file_type().is_file());
+    assert!(output.join(IP).join("evil.txt").is_file());
+}
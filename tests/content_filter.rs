@@ -0,0 +1,99 @@
+//! Tests for the --accept-mime, --reject-mime, --min-size and --max-size options
+
+mod fixtures;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const HTML: &str = "text/html; charset=utf-8";
+const PHOTO: &str = "A photo of medium size, between the limits of the tests";
+const VIDEO: &str =
+    "A video which is way too large for the size limits of the tests, so its download \
+    is aborted before it is saved, and the rest of the site is still downloaded normally";
+
+const FILES: &[(&str, &str, &str)] = &[
+    (
+        "/",
+        HTML,
+        concat!(
+            "<a href=\"report\">Report</a><a href=\"notes\">Notes</a>",
+            "<a href=\"draft\">Draft</a>",
+            "<a href=\"gallery/\">Gallery</a>",
+        ),
+    ),
+    ("/report", "application/pdf", PHOTO),
+    ("/notes", "text/plain", PHOTO),
+    // Sent without a type
+    ("/draft", "", PHOTO),
+    (
+        "/gallery/animation",
+        "",
+        "GIF89a, an animation sent without a type",
+    ),
+    (
+        "/gallery/",
+        HTML,
+        "<img src=\"photo\"><img src=\"icon\"><img src=\"animation\"><video src=\"video\"></video>",
+    ),
+    ("/gallery/photo", "image/jpeg", PHOTO),
+    ("/gallery/icon", "image/png", "x"),
+    ("/gallery/video", "video/mp4", VIDEO),
+];
+
+fn scrape(extra_args: &[&str]) -> mktemp::Temp {
+    let ip = fixtures::spawn_site_http_server(FILES);
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tempdir
+}
+
+fn is_downloaded(output_dir: &mktemp::Temp, path: &str) -> bool {
+    Path::new(output_dir.as_path())
+        .join("0.0.0.0")
+        .join(path)
+        .exists()
+}
+
+#[test]
+fn media_types() {
+    let output_dir = scrape(&[
+        "--accept-mime",
+        "image/*,application/pdf",
+        "--reject-mime",
+        "image/png",
+    ]);
+
+    assert!(is_downloaded(&output_dir, "report"));
+    assert!(is_downloaded(&output_dir, "gallery/photo"));
+    assert!(!is_downloaded(&output_dir, "gallery/icon"));
+    assert!(!is_downloaded(&output_dir, "gallery/video"));
+    assert!(!is_downloaded(&output_dir, "notes"));
+    // The untyped files are filtered by their sniffed type
+    assert!(is_downloaded(&output_dir, "gallery/animation"));
+    assert!(!is_downloaded(&output_dir, "draft"));
+    // The pages are visited, but not saved
+    assert!(!is_downloaded(&output_dir, "index.html"));
+    assert!(!is_downloaded(&output_dir, "gallery/index.html"));
+}
+
+#[test]
+fn sizes() {
+    let output_dir = scrape(&["--min-size", "10", "--max-size", "150"]);
+
+    assert!(is_downloaded(&output_dir, "gallery/photo"));
+    assert!(!is_downloaded(&output_dir, "gallery/icon"));
+    assert!(!is_downloaded(&output_dir, "gallery/video"));
+    assert!(is_downloaded(&output_dir, "gallery/index.html"));
+}
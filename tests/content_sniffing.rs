@@ -0,0 +1,33 @@
+//! Tests for content sniffing when the server sends no Content-Type
+
+mod fixtures;
+
+use std::fs;
+use std::process::{Command, Stdio};
+
+const PAGE: &str = "tests/fixtures/";
+const IP: &str = "0.0.0.0";
+
+// Binary files must not go through the HTML parser
+#[test]
+fn binary_without_content_type() {
+    let ip = fixtures::spawn_local_http_server(PAGE, false, None);
+    let url = format!("http://{}", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir])
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .unwrap();
+    let status = cmd.wait().unwrap();
+    assert!(status.success());
+
+    let data_source = fs::read(format!("{}jpeg.jpg", PAGE)).unwrap();
+    let data_downloaded = fs::read(format!("{}/{}/jpeg.jpg", output_dir, IP)).unwrap();
+
+    assert!(fixtures::do_vecs_match(&data_source, &data_downloaded));
+}
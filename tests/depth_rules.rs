@@ -0,0 +1,53 @@
+//! Tests for the --depth-rule option
+
+mod fixtures;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[test]
+fn depth_rules() {
+    let ip = fixtures::spawn_linking_http_server(|_, path| {
+        if path == "/" {
+            return "<a href=\"/blog/page/1.html\">Blog</a><a href=\"/tags/a.html\">Tag</a>"
+                .to_string();
+        }
+        if path.starts_with("/tags/") {
+            return "<img src=\"/tags/icon.png\"><a href=\"/tags/b.html\">Other tag</a>"
+                .to_string();
+        }
+
+        let page: u32 = path
+            .trim_start_matches("/blog/page/")
+            .trim_end_matches(".html")
+            .parse()
+            .unwrap_or(0);
+        format!("<a href=\"/blog/page/{}.html\">Next</a>", page + 1)
+    });
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "-d", "2"])
+        .args(["--depth-rule", "/blog/page/=5"])
+        .args(["--depth-rule", "/tags/=nofollow"])
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let host_dir = Path::new(output_dir).join("0.0.0.0");
+    for page in 1..=5 {
+        let path = format!("blog/page/{}.html", page);
+        assert!(host_dir.join(&path).is_file(), "{} is missing", path);
+    }
+    assert!(!host_dir.join("blog/page/6.html").exists());
+
+    // The requisites of nofollow pages are still downloaded
+    assert!(host_dir.join("tags/a.html").is_file());
+    assert!(host_dir.join("tags/icon.png").is_file());
+    assert!(!host_dir.join("tags/b.html").exists());
+}
@@ -2,14 +2,12 @@
 
 mod fixtures;
 
-use fixtures::get_file_count_with_pattern;
 use std::fs::read_dir;
 use std::process::Command;
 use std::process::Stdio;
-use std::sync::Once;
 
-const PAGE: &'static str = "tests/fixtures/";
-const IP: &'static str = "0.0.0.0";
+const PAGE: &str = "tests/fixtures/";
+const IP: &str = "0.0.0.0";
 
 // Test to use include flag for downloading pages only matching the given pattern.
 #[test]
@@ -24,7 +22,7 @@ fn with_external() {
     let external = format!("{}/{}/", output_dir, "google.com");
 
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir, "-d", "0", "--ext-depth", "1"])
+        .args([&url, "-o", output_dir, "-d", "0", "--ext-depth", "1"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -48,7 +46,7 @@ fn without_external() {
 
     let external = format!("{}/{}/", output_dir, "google.com");
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir, "-d", "0", "--ext-depth", "0"])
+        .args([&url, "-o", output_dir, "-d", "0", "--ext-depth", "0"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
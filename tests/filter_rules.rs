@@ -0,0 +1,113 @@
+//! Tests for repeated filters, the --accept and --reject globs, and the rule files
+
+mod fixtures;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const HTML: &str = "text/html; charset=utf-8";
+
+const FILES: &[(&str, &str, &str)] = &[
+    (
+        "/",
+        HTML,
+        concat!(
+            "<a href=\"docs/\">Docs</a><a href=\"blog/\">Blog</a>",
+            "<a href=\"report.pdf\">Report</a><a href=\"notes.txt\">Notes</a>",
+            "<a href=\"logo.png\">Logo</a><a href=\"archive.zip\">Archive</a>",
+        ),
+    ),
+    (
+        "/docs/",
+        HTML,
+        "<a href=\"manual.pdf\">Manual</a><a href=\"private/\">Private</a>",
+    ),
+    ("/docs/manual.pdf", "application/pdf", "manual"),
+    ("/docs/private/", HTML, "<a href=\"keys.pdf\">Keys</a>"),
+    ("/docs/private/keys.pdf", "application/pdf", "keys"),
+    ("/blog/", HTML, "<a href=\"post.pdf\">Post</a>"),
+    ("/blog/post.pdf", "application/pdf", "post"),
+    ("/report.pdf", "application/pdf", "report"),
+    ("/notes.txt", "text/plain", "notes"),
+    ("/logo.png", "image/png", "logo"),
+    ("/archive.zip", "application/zip", "archive"),
+];
+
+fn scrape(extra_args: &[&str]) -> mktemp::Temp {
+    let ip = fixtures::spawn_site_http_server(FILES);
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tempdir
+}
+
+fn is_downloaded(output_dir: &mktemp::Temp, path: &str) -> bool {
+    Path::new(output_dir.as_path())
+        .join("0.0.0.0")
+        .join(path)
+        .is_file()
+}
+
+#[test]
+fn repeated_regexes() {
+    let output_dir = scrape(&["-i", "pdf$", "-i", "png$", "-e", "blog", "-e", "private"]);
+
+    assert!(is_downloaded(&output_dir, "report.pdf"));
+    assert!(is_downloaded(&output_dir, "logo.png"));
+    assert!(is_downloaded(&output_dir, "docs/manual.pdf"));
+    assert!(!is_downloaded(&output_dir, "blog/post.pdf"));
+    assert!(!is_downloaded(&output_dir, "docs/private/keys.pdf"));
+    assert!(!is_downloaded(&output_dir, "notes.txt"));
+    assert!(!is_downloaded(&output_dir, "index.html"));
+}
+
+#[test]
+fn globs() {
+    let output_dir = scrape(&[
+        "--accept", "*.pdf", "--accept", "*.zip", "--reject", "/blog/**",
+    ]);
+
+    assert!(is_downloaded(&output_dir, "report.pdf"));
+    assert!(is_downloaded(&output_dir, "archive.zip"));
+    assert!(is_downloaded(&output_dir, "docs/private/keys.pdf"));
+    assert!(!is_downloaded(&output_dir, "blog/post.pdf"));
+    assert!(!is_downloaded(&output_dir, "logo.png"));
+}
+
+#[test]
+fn rule_files() {
+    let rules_dir = mktemp::Temp::new_dir().unwrap();
+    let visit_rules = rules_dir.as_path().join("visit.rules");
+    fs::write(
+        &visit_rules,
+        "# Stay out of the private documentation\ndeny /private/\nallow glob:/docs/**\ndeny blog\n",
+    )
+    .unwrap();
+    let download_rules = rules_dir.as_path().join("download.rules");
+    fs::write(&download_rules, "allow glob:/docs/*.pdf\ndeny glob:*.pdf\n").unwrap();
+
+    let output_dir = scrape(&[
+        "--visit-rules",
+        visit_rules.to_str().unwrap(),
+        "--download-rules",
+        download_rules.to_str().unwrap(),
+    ]);
+
+    assert!(is_downloaded(&output_dir, "docs/manual.pdf"));
+    assert!(is_downloaded(&output_dir, "notes.txt"));
+    assert!(!is_downloaded(&output_dir, "report.pdf"));
+    assert!(!is_downloaded(&output_dir, "docs/private/index.html"));
+    assert!(!is_downloaded(&output_dir, "blog/index.html"));
+}
@@ -6,11 +6,9 @@ use fixtures::get_file_count_with_pattern;
 use std::fs::read_dir;
 use std::process::Command;
 use std::process::Stdio;
-use std::sync::Once;
 
-const PAGE: &'static str = "tests/fixtures/";
-const IP: &'static str = "0.0.0.0";
-static START: Once = Once::new();
+const PAGE: &str = "tests/fixtures/";
+const IP: &str = "0.0.0.0";
 
 #[test]
 fn visit_filter_is_download_filter() {
@@ -21,8 +19,8 @@ fn visit_filter_is_download_filter() {
     let output_dir = tempdir.to_str().unwrap();
 
     let files_dir = format!("{}/{}/", output_dir, IP);
-    let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[
+    let cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([
             &url,
             "-o",
             output_dir,
@@ -40,7 +38,7 @@ fn visit_filter_is_download_filter() {
     let stdout_str = unsafe { String::from_utf8_unchecked(result.stdout) };
     assert!(result.status.success());
 
-    let paths = read_dir(&files_dir).unwrap();
+    let _paths = read_dir(&files_dir).unwrap();
 
     assert!(!stdout_str.contains("should_not_get_visited.html"));
 }
@@ -56,7 +54,7 @@ fn visit_include_filter() {
 
     let files_dir = format!("{}/{}/", output_dir, IP);
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir, "--include-visit", "mp[3-4]"])
+        .args([&url, "-o", output_dir, "--include-visit", "mp[3-4]"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -83,7 +81,7 @@ fn visit_include_multiple_filters() {
 
     let files_dir = format!("{}/{}/", output_dir, IP);
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir, "--include-visit", "(mp[3-4])|(txt)"])
+        .args([&url, "-o", output_dir, "--include-visit", "(mp[3-4])|(txt)"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -110,7 +108,7 @@ fn visit_exclude_filter() {
 
     let files_dir = format!("{}/{}/", output_dir, IP);
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir, "--exclude-visit", "jpe?g"])
+        .args([&url, "-o", output_dir, "--exclude-visit", "jpe?g"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -134,7 +132,7 @@ fn download_include_filter() {
 
     let files_dir = format!("{}/{}/", output_dir, IP);
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir, "-i", "mp[3-4]"])
+        .args([&url, "-o", output_dir, "-i", "mp[3-4]"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -163,7 +161,7 @@ fn download_include_multiple_filters() {
 
     let files_dir = format!("{}/{}/", output_dir, IP);
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir, "-i", "(mp[3-4])|(txt)"])
+        .args([&url, "-o", output_dir, "-i", "(mp[3-4])|(txt)"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -188,7 +186,7 @@ fn download_exclude_filter() {
 
     let files_dir = format!("{}/{}/", output_dir, IP);
     let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
-        .args(&[&url, "-o", output_dir, "-e", "jpe?g"])
+        .args([&url, "-o", output_dir, "-e", "jpe?g"])
         .stdout(Stdio::inherit())
         .stderr(Stdio::inherit())
         .spawn()
@@ -196,7 +194,7 @@ fn download_exclude_filter() {
 
     let status = cmd.wait().unwrap();
     assert!(status.success());
-    let paths = read_dir(&files_dir).unwrap();
+    let _paths = read_dir(&files_dir).unwrap();
     let jpeg_count = get_file_count_with_pattern(".jpe?g", &files_dir).unwrap();
     assert_eq!(jpeg_count, 0);
 }
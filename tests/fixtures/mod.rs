@@ -1,7 +1,10 @@
+#![allow(dead_code)]
+
 use std::fs::File;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 use std::thread;
 
-use portpicker;
 use subprocess::Exec;
 use tiny_http::{Header, Response, Server};
 
@@ -39,26 +42,168 @@ pub fn spawn_local_http_server(
                 Response::from_file(File::open(file).unwrap()).boxed()
             };
 
-            match headers {
-                Some(vec) => {
-                    let mut key_vec: Vec<u8> = vec![];
-                    let mut value_vec: Vec<u8> = vec![];
-                    for (key, value) in vec {
-                        key_vec.extend_from_slice(key.as_bytes());
-                        value_vec.extend_from_slice(value.as_bytes());
-                    }
-
-                    let h = Header::from_bytes(key_vec, value_vec).unwrap();
+            if let Some(vec) = headers {
+                for (key, value) in vec {
+                    let h = Header::from_bytes(key.as_bytes(), value.as_bytes()).unwrap();
                     response.add_header(h);
                 }
-                _ => (),
             }
 
             request.respond(response).unwrap();
         }
     });
 
-    return addr;
+    addr
+}
+
+/// Spawn a server serving the files of `page` with the given headers, and counting the requests
+pub fn spawn_counting_http_server(
+    page: &'static str,
+    headers: &'static [(&'static str, &'static str)],
+) -> (String, Arc<AtomicUsize>) {
+    let port = portpicker::pick_unused_port().unwrap();
+    let addr = format!("0.0.0.0:{}", port);
+    let server = Server::http(&addr).unwrap();
+    let count = Arc::new(AtomicUsize::new(0));
+    let server_count = count.clone();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            server_count.fetch_add(1, Ordering::SeqCst);
+
+            let file = match request.url() {
+                "/" => format!("{}{}", page, "index.html"),
+                other => format!("{}{}", page, other),
+            };
+            let mut response = match File::open(file) {
+                Ok(file) => Response::from_file(file).boxed(),
+                Err(_) => Response::from_string("Not found")
+                    .with_status_code(404)
+                    .boxed(),
+            };
+            for (key, value) in headers {
+                let h = Header::from_bytes(key.as_bytes(), value.as_bytes()).unwrap();
+                response.add_header(h);
+            }
+
+            request.respond(response).unwrap();
+        }
+    });
+
+    (addr, count)
+}
+
+/// Spawn a server answering every HTML page with the body returned by `body`, which is
+/// given the port of the server and the requested path. Other paths are text files
+pub fn spawn_linking_http_server<F>(body: F) -> String
+where
+    F: Fn(u16, &str) -> String + Send + 'static,
+{
+    let port = portpicker::pick_unused_port().unwrap();
+    let addr = format!("0.0.0.0:{}", port);
+    let server = Server::http(&addr).unwrap();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let path = request.url().to_string();
+            let response = if path.ends_with('/') || path.ends_with(".html") {
+                let h = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+                Response::from_string(format!("<html><body>{}</body></html>", body(port, &path)))
+                    .with_header(h)
+            } else {
+                let h = Header::from_bytes("Content-Type", "text/plain").unwrap();
+                Response::from_string(format!("Content of {}", path)).with_header(h)
+            };
+
+            request.respond(response).unwrap();
+        }
+    });
+
+    addr
+}
+
+/// Spawn a server answering every path with an HTML page, whose status and body are
+/// returned by `page` from the requested path
+pub fn spawn_html_http_server<F>(page: F) -> String
+where
+    F: Fn(&str) -> (u16, String) + Send + 'static,
+{
+    let port = portpicker::pick_unused_port().unwrap();
+    let addr = format!("0.0.0.0:{}", port);
+    let server = Server::http(&addr).unwrap();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let (status, body) = page(request.url());
+            let h = Header::from_bytes("Content-Type", "text/html; charset=utf-8").unwrap();
+            let response = Response::from_string(format!("<html><body>{}</body></html>", body))
+                .with_status_code(status)
+                .with_header(h);
+
+            request.respond(response).unwrap();
+        }
+    });
+
+    addr
+}
+
+/// Spawn a server serving the given files, made of a path, a content type and a body in
+/// which `{PORT}` is replaced by the port of the server. An empty content type sends no
+/// `Content-Type` header. Other paths are not found
+pub fn spawn_site_http_server(
+    files: &'static [(&'static str, &'static str, &'static str)],
+) -> String {
+    let port = portpicker::pick_unused_port().unwrap();
+    let addr = format!("0.0.0.0:{}", port);
+    let server = Server::http(&addr).unwrap();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let response = match files.iter().find(|(path, _, _)| *path == request.url()) {
+                Some((_, content_type, body)) => {
+                    let body = body.replace("{PORT}", &port.to_string());
+                    if content_type.is_empty() {
+                        Response::from_data(body.into_bytes())
+                    } else {
+                        let h = Header::from_bytes("Content-Type", *content_type).unwrap();
+                        Response::from_string(body).with_header(h)
+                    }
+                }
+                None => Response::from_string("Not found").with_status_code(404),
+            };
+
+            request.respond(response).unwrap();
+        }
+    });
+
+    addr
+}
+
+/// Spawn a server which accepts requests but never answers them
+pub fn spawn_unresponsive_http_server() -> String {
+    let port = portpicker::pick_unused_port().unwrap();
+    let addr = format!("0.0.0.0:{}", port);
+    let server = Server::http(&addr).unwrap();
+    thread::spawn(move || {
+        let mut pending = Vec::new();
+        for request in server.incoming_requests() {
+            pending.push(request);
+        }
+    });
+
+    addr
+}
+
+/// Spawn a server which redirects every request to the given location
+pub fn spawn_redirecting_http_server(location: &'static str) -> String {
+    let port = portpicker::pick_unused_port().unwrap();
+    let addr = format!("0.0.0.0:{}", port);
+    let server = Server::http(&addr).unwrap();
+    thread::spawn(move || {
+        for request in server.incoming_requests() {
+            let h = Header::from_bytes("Location", location).unwrap();
+            let response = Response::empty(302).with_header(h);
+            request.respond(response).unwrap();
+        }
+    });
+
+    addr
 }
 
 fn check_auth_credentials(auth_header: Option<&Header>) -> bool {
@@ -89,7 +234,7 @@ pub fn get_file_count_with_pattern(pattern: &str, dir: &str) -> Result<usize, ()
     }
 }
 
-pub fn do_vecs_match<T: PartialEq>(a: &Vec<T>, b: &Vec<T>) -> bool {
+pub fn do_vecs_match<T: PartialEq>(a: &[T], b: &[T]) -> bool {
     let matching = a.iter().zip(b.iter()).filter(|&(a, b)| a == b).count();
     matching == a.len() && matching == b.len()
 }
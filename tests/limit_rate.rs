@@ -0,0 +1,45 @@
+//! Tests for the --limit-rate flags
+
+mod fixtures;
+
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const PAGE: &str = "tests/fixtures/";
+
+fn timed_download(extra_args: &[&str]) -> Duration {
+    let ip = fixtures::spawn_local_http_server(PAGE, false, None);
+    let url = format!("http://{}", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let start = Instant::now();
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "-d", "0"])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    start.elapsed()
+}
+
+// index.html is 466 bytes long, at 100 bytes per second the last 366 take more than 3 seconds
+#[test]
+fn limit_rate() {
+    let unlimited = timed_download(&[]);
+    let limited = timed_download(&["--limit-rate", "100"]);
+
+    assert!(limited >= unlimited + Duration::from_secs(3));
+}
+
+#[test]
+fn limit_rate_per_host() {
+    let unlimited = timed_download(&[]);
+    let limited = timed_download(&["--limit-rate", "1M", "--limit-rate-per-host", "100"]);
+
+    assert!(limited >= unlimited + Duration::from_secs(3));
+}
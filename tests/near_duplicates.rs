@@ -0,0 +1,103 @@
+//! Tests for the --near-duplicate-distance option
+
+mod fixtures;
+
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const ARTICLE: &str = "SuckIT downloads a website to your disk, following the links of every \
+    page and rewriting them so the copy can be browsed offline. It is fast thanks to its \
+    multiple threads, and it can throttle itself to avoid hammering servers. The pages are \
+    saved in a directory named after their host, and the links between them are made \
+    relative. Requisites like images, stylesheets and scripts are downloaded too, so the pages \
+    look the same as online. A random delay can be added between the downloads, and the \
+    scraping can be resumed later from the cache. Filters decide which pages are visited and \
+    which ones are saved, with regular expressions matching their URLs.";
+
+// The article is served twice, the print view having one more word and its own link
+fn scrape(extra_args: &[&str]) -> mktemp::Temp {
+    let ip = fixtures::spawn_linking_http_server(|_, path| match path {
+        "/" => "<a href=\"article.html\">Article</a><a href=\"print.html\">Print</a>".to_string(),
+        "/article.html" => format!("<p>{}</p><a href=\"comments.html\">Comments</a>", ARTICLE),
+        "/print.html" => format!("<p>{}</p><a href=\"tags.html\">Comments</a> Print", ARTICLE),
+        _ => String::new(),
+    });
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tempdir
+}
+
+#[test]
+fn disabled() {
+    let output_dir = scrape(&[]);
+    let host_dir = Path::new(output_dir.as_path()).join("0.0.0.0");
+
+    assert!(host_dir.join("comments.html").is_file());
+    assert!(host_dir.join("tags.html").is_file());
+}
+
+#[test]
+fn linked_to_original() {
+    let output_dir = scrape(&["--near-duplicate-distance", "6"]);
+    let host_dir = Path::new(output_dir.as_path()).join("0.0.0.0");
+
+    assert!(host_dir.join("comments.html").is_file());
+    assert!(!host_dir.join("tags.html").exists());
+
+    let print = host_dir.join("print.html");
+    assert!(fs::symlink_metadata(&print)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert_eq!(
+        fs::read_link(&print).unwrap(),
+        Path::new("article.html").to_path_buf()
+    );
+}
+
+#[test]
+fn copied_with_no_symlinks() {
+    let output_dir = scrape(&["--near-duplicate-distance", "6", "--no-symlinks"]);
+    let host_dir = Path::new(output_dir.as_path()).join("0.0.0.0");
+
+    let print = host_dir.join("print.html");
+    assert!(!fs::symlink_metadata(&print)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert!(fs::read_to_string(&print).unwrap().contains("tags.html"));
+    assert!(!host_dir.join("tags.html").exists());
+}
+
+#[test]
+fn original_not_saved() {
+    let output_dir = scrape(&[
+        "--near-duplicate-distance",
+        "6",
+        "--exclude-download",
+        "article",
+    ]);
+    let host_dir = Path::new(output_dir.as_path()).join("0.0.0.0");
+
+    // The print view is the first saved copy of the article, so it is saved in full
+    let print = host_dir.join("print.html");
+    assert!(!host_dir.join("article.html").exists());
+    assert!(!fs::symlink_metadata(&print)
+        .unwrap()
+        .file_type()
+        .is_symlink());
+    assert!(host_dir.join("tags.html").is_file());
+}
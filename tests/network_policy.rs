@@ -0,0 +1,120 @@
+//! Tests for the --deny-private-networks and --allow-network flags
+
+mod fixtures;
+
+use std::fs::read_dir;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const PAGE: &str = "tests/fixtures/";
+const FAKE_HOST: &str = "suckit.test";
+const METADATA_URL: &str = "http://169.254.169.254/latest/meta-data/";
+
+fn run_suckit(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args(args)
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+// Loopback addresses written in the URL are refused
+#[test]
+fn deny_loopback_address() {
+    let ip = fixtures::spawn_local_http_server(PAGE, false, None);
+    let url = format!("http://{}", ip.replace("0.0.0.0", "127.0.0.1"));
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let stdout = run_suckit(&[&url, "-o", output_dir, "--deny-private-networks"]);
+
+    assert!(stdout.contains("Refused to download"));
+    assert_eq!(read_dir(output_dir).unwrap().count(), 0);
+}
+
+// Domains resolving to loopback addresses are refused, unless allowed
+#[test]
+fn deny_resolved_address() {
+    let ip = fixtures::spawn_local_http_server(PAGE, false, None);
+    let port = ip.rsplit(':').next().unwrap();
+    let url = format!("http://{}:{}/", FAKE_HOST, port);
+    let resolve = format!("{}:*:127.0.0.1", FAKE_HOST);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+    let args = [&url, "-o", output_dir, "--resolve", &resolve];
+
+    let stdout = run_suckit(&[&args[..], &["--deny-private-networks"]].concat());
+    assert!(stdout.contains("restricted address 127.0.0.1"));
+    assert_eq!(read_dir(output_dir).unwrap().count(), 0);
+
+    run_suckit(
+        &[
+            &args[..],
+            &["--deny-private-networks", "--allow-network", "127.0.0.0/8"],
+        ]
+        .concat(),
+    );
+    assert!(Path::new(output_dir)
+        .join(FAKE_HOST)
+        .join("index.html")
+        .is_file());
+}
+
+// Redirections are checked at every hop
+#[test]
+fn deny_redirection() {
+    let ip = fixtures::spawn_redirecting_http_server(METADATA_URL);
+    let url = format!("http://{}", ip.replace("0.0.0.0", "127.0.0.1"));
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let stdout = run_suckit(&[
+        &url,
+        "-o",
+        output_dir,
+        "--deny-private-networks",
+        "--allow-network",
+        "127.0.0.1",
+    ]);
+
+    assert!(stdout.contains("restricted address 169.254.169.254"));
+    assert_eq!(read_dir(output_dir).unwrap().count(), 0);
+}
+
+// The proxies of the environment would resolve the hosts out of reach of the policy
+#[test]
+fn ignore_proxy() {
+    let (proxy, proxied) = fixtures::spawn_counting_http_server(PAGE, &[]);
+    let proxy = format!("http://{}", proxy.replace("0.0.0.0", "127.0.0.1"));
+    let url = format!("http://{}/", FAKE_HOST);
+    let resolve = format!("{}:*:127.0.0.1", FAKE_HOST);
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([
+            &url,
+            "-o",
+            output_dir,
+            "--resolve",
+            &resolve,
+            "--deny-private-networks",
+        ])
+        .env("HTTP_PROXY", &proxy)
+        .env("ALL_PROXY", &proxy)
+        .env_remove("NO_PROXY")
+        .env_remove("no_proxy")
+        .stderr(Stdio::inherit())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(stdout.contains("restricted address 127.0.0.1"));
+    assert_eq!(proxied.load(std::sync::atomic::Ordering::SeqCst), 0);
+    assert_eq!(read_dir(output_dir).unwrap().count(), 0);
+}
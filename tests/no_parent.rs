@@ -0,0 +1,76 @@
+//! Tests for the --no-parent, --scope-prefix and --requisites-outside-prefix flags
+
+mod fixtures;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const IP: &str = "0.0.0.0";
+
+// The documentation links to its pages, to the rest of the site and to a shared image
+fn scrape_docs(extra_args: &[&str]) -> mktemp::Temp {
+    let ip = fixtures::spawn_linking_http_server(|_, path| match path {
+        "/docs/v2/" => concat!(
+            "<a href=\"intro.html\">Intro</a>",
+            "<a href=\"/docs/v1/\">Previous version</a>",
+            "<a href=\"/blog/\">Blog</a>",
+            "<a href=\"/static/notes.txt\">Notes</a>",
+            "<img src=\"/static/logo.png\">",
+        )
+        .to_string(),
+        _ => String::new(),
+    });
+    let url = format!("http://{}/docs/v2/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tempdir
+}
+
+fn is_downloaded(output_dir: &mktemp::Temp, path: &str) -> bool {
+    Path::new(output_dir.as_path())
+        .join(IP)
+        .join(path)
+        .is_file()
+}
+
+#[test]
+fn no_parent() {
+    let output_dir = scrape_docs(&["--no-parent"]);
+
+    assert!(is_downloaded(&output_dir, "docs/v2/index.html"));
+    assert!(is_downloaded(&output_dir, "docs/v2/intro.html"));
+    assert!(!is_downloaded(&output_dir, "docs/v1/index.html"));
+    assert!(!is_downloaded(&output_dir, "blog/index.html"));
+    assert!(!is_downloaded(&output_dir, "static/logo.png"));
+}
+
+#[test]
+fn requisites_outside_prefix() {
+    let output_dir = scrape_docs(&["--no-parent", "--requisites-outside-prefix"]);
+
+    assert!(is_downloaded(&output_dir, "docs/v2/intro.html"));
+    assert!(is_downloaded(&output_dir, "static/logo.png"));
+    assert!(!is_downloaded(&output_dir, "static/notes.txt"));
+    assert!(!is_downloaded(&output_dir, "blog/index.html"));
+}
+
+#[test]
+fn scope_prefix() {
+    let output_dir = scrape_docs(&["--no-parent", "--scope-prefix", "/static/"]);
+
+    assert!(is_downloaded(&output_dir, "docs/v2/intro.html"));
+    assert!(is_downloaded(&output_dir, "static/logo.png"));
+    assert!(is_downloaded(&output_dir, "static/notes.txt"));
+    assert!(!is_downloaded(&output_dir, "docs/v1/index.html"));
+}
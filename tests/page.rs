@@ -0,0 +1,79 @@
+//! Tests for the --page flag
+
+mod fixtures;
+
+use std::fs::read_to_string;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const HTML: &str = "text/html; charset=utf-8";
+
+const FILES: &[(&str, &str, &str)] = &[
+    (
+        "/article.html",
+        HTML,
+        concat!(
+            "<link rel=\"stylesheet\" href=\"style.css\">",
+            "<img src=\"http://cdn.test:{PORT}/photo.jpg\">",
+            "<iframe src=\"frame.html\"></iframe>",
+            "<a href=\"#comments\">Comments</a>",
+            "<a href=\"other.html\">Other article</a>",
+        ),
+    ),
+    (
+        "/style.css",
+        "text/css",
+        "body { background: url(bg.png); }",
+    ),
+    ("/bg.png", "image/png", "bg"),
+    (
+        "/frame.html",
+        HTML,
+        "<img src=\"map.png\"><iframe src=\"nested.html\"></iframe>",
+    ),
+    ("/map.png", "image/png", "map"),
+    ("/nested.html", HTML, "Too deep"),
+    ("/other.html", HTML, "Another article"),
+    ("/photo.jpg", "image/jpeg", "photo"),
+];
+
+#[test]
+fn single_page() {
+    let ip = fixtures::spawn_site_http_server(FILES);
+    let url = format!("http://{}/article.html", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "--page"])
+        .args(["--resolve", "cdn.test:*:127.0.0.1"])
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let host_dir = Path::new(output_dir).join("0.0.0.0");
+    for path in [
+        "article.html",
+        "style.css",
+        "bg.png",
+        "frame.html",
+        "map.png",
+    ] {
+        assert!(host_dir.join(path).is_file(), "{} is missing", path);
+    }
+    assert!(Path::new(output_dir).join("cdn.test/photo.jpg").is_file());
+    assert!(!host_dir.join("other.html").exists());
+    assert!(!host_dir.join("nested.html").exists());
+
+    let article = read_to_string(host_dir.join("article.html")).unwrap();
+    assert!(article.contains("href=\"style.css\""));
+    assert!(article.contains("src=\"../cdn.test/photo.jpg\""));
+    assert!(article.contains("href=\"#comments\""));
+    assert!(article.contains(&format!("href=\"http://{}/other.html\"", ip)));
+
+    let frame = read_to_string(host_dir.join("frame.html")).unwrap();
+    assert!(frame.contains(&format!("src=\"http://{}/nested.html\"", ip)));
+}
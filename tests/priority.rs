@@ -0,0 +1,57 @@
+//! Tests for the --strategy and --priority options
+
+mod fixtures;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+// The home page links to pages of various lengths, with the important ones last
+fn scrape(extra_args: &[&str]) -> mktemp::Temp {
+    let ip = fixtures::spawn_linking_http_server(|_, path| match path {
+        "/" => concat!(
+            "<a href=\"/archives/2019/01/old-post.html\">Old</a>",
+            "<a href=\"/archives/2020/05/other-post.html\">Other</a>",
+            "<a href=\"/about.html\">About</a>",
+            "<a href=\"/docs/guide.html\">Guide</a>",
+        )
+        .to_string(),
+        _ => "Leaf".to_string(),
+    });
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "-j", "1", "--max-pages", "3"])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tempdir
+}
+
+#[test]
+fn shortest_url() {
+    let tempdir = scrape(&["--strategy", "shortest-url"]);
+    let host_dir = Path::new(tempdir.to_str().unwrap()).join("0.0.0.0");
+
+    assert!(host_dir.join("index.html").is_file());
+    assert!(host_dir.join("about.html").is_file());
+    assert!(host_dir.join("docs/guide.html").is_file());
+    assert!(!host_dir.join("archives").exists());
+}
+
+#[test]
+fn priority_rules() {
+    let tempdir = scrape(&["--priority", "/docs/=10", "--priority", "/2020/=5"]);
+    let host_dir = Path::new(tempdir.to_str().unwrap()).join("0.0.0.0");
+
+    assert!(host_dir.join("docs/guide.html").is_file());
+    assert!(host_dir.join("archives/2020/05/other-post.html").is_file());
+    assert!(!host_dir.join("about.html").exists());
+    assert!(!host_dir.join("archives/2019").exists());
+}
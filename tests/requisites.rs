@@ -0,0 +1,80 @@
+//! Tests for the download of page requisites, which doesn't consume any depth
+
+mod fixtures;
+
+use std::fs::read_to_string;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const HTML: &str = "text/html; charset=utf-8";
+const CSS: &str = "text/css";
+
+const FILES: &[(&str, &str, &str)] = &[
+    ("/", HTML, "<a href=\"page.html\">Page</a>"),
+    (
+        "/page.html",
+        HTML,
+        concat!(
+            "<link rel=\"stylesheet\" href=\"css/style.css\">",
+            "<style>h1 { background: url(/img/title.png); }</style>",
+            "<img src=\"img/logo.png\"><img src=\"http://cdn.test:{PORT}/photo.jpg\">",
+            "<a href=\"next.html\">Next</a>",
+        ),
+    ),
+    ("/next.html", HTML, "The end"),
+    (
+        "/css/style.css",
+        CSS,
+        "@import 'print.css'; body { background: url(\"../img/bg.png\"); }",
+    ),
+    (
+        "/css/print.css",
+        CSS,
+        "@font-face { src: url(/fonts/icons.woff2); }",
+    ),
+    ("/img/logo.png", "image/png", "logo"),
+    ("/img/title.png", "image/png", "title"),
+    ("/img/bg.png", "image/png", "bg"),
+    ("/fonts/icons.woff2", "font/woff2", "font"),
+    ("/photo.jpg", "image/jpeg", "photo"),
+];
+
+#[test]
+fn requisites_of_last_level() {
+    let ip = fixtures::spawn_site_http_server(FILES);
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "-d", "1"])
+        .args(["--resolve", "cdn.test:*:127.0.0.1"])
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let host_dir = Path::new(output_dir).join("0.0.0.0");
+    for path in [
+        "page.html",
+        "css/style.css",
+        "css/print.css",
+        "img/logo.png",
+        "img/title.png",
+        "img/bg.png",
+        "fonts/icons.woff2",
+    ] {
+        assert!(host_dir.join(path).is_file(), "{} is missing", path);
+    }
+    assert!(Path::new(output_dir).join("cdn.test/photo.jpg").is_file());
+    assert!(!host_dir.join("next.html").exists());
+
+    let page = read_to_string(host_dir.join("page.html")).unwrap();
+    assert!(page.contains("url(img/title.png)"));
+    let style = read_to_string(host_dir.join("css/style.css")).unwrap();
+    assert!(style.contains("url(\"../img/bg.png\")"));
+    let print = read_to_string(host_dir.join("css/print.css")).unwrap();
+    assert!(print.contains("url(../fonts/icons.woff2)"));
+}
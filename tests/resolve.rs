@@ -0,0 +1,34 @@
+//! Tests for the --resolve flag
+
+mod fixtures;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const PAGE: &str = "tests/fixtures/";
+const FAKE_HOST: &str = "suckit.test";
+
+// A fake domain can be served by the local server, and keeps its name in the output
+#[test]
+fn resolve_fake_domain() {
+    let ip = fixtures::spawn_local_http_server(PAGE, false, None);
+    let port = ip.rsplit(':').next().unwrap();
+    let url = format!("http://{}:{}/", FAKE_HOST, port);
+    let resolve = format!("{}:*:127.0.0.1", FAKE_HOST);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let mut cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "--resolve", &resolve])
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .unwrap();
+    let status = cmd.wait().unwrap();
+    assert!(status.success());
+
+    let files_dir = Path::new(output_dir).join(FAKE_HOST);
+    assert!(files_dir.join("index.html").is_file());
+    assert!(files_dir.join("file.txt").is_file());
+}
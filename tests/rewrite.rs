@@ -0,0 +1,61 @@
+//! Tests for the --rewrite option
+
+mod fixtures;
+
+use std::fs::read_to_string;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const PRINT_RULE: &str = r"^(http://[^/]+/articles/[^?]*)$ $1?format=print";
+
+// The home page links to an article, which has a lighter print version
+fn scrape(extra_args: &[&str]) -> mktemp::Temp {
+    let ip = fixtures::spawn_html_http_server(|path| match path {
+        "/" => (200, "<a href=\"articles/a.html\">Article</a>".to_string()),
+        "/articles/a.html" => (200, "Article with a lot of scripts".to_string()),
+        "/articles/a.html?format=print" => (200, "Printable article".to_string()),
+        _ => (404, "Not found".to_string()),
+    });
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "--rewrite", PRINT_RULE])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tempdir
+}
+
+#[test]
+fn rewritten_paths() {
+    let tempdir = scrape(&[]);
+    let host_dir = Path::new(tempdir.to_str().unwrap()).join("0.0.0.0");
+
+    let index = read_to_string(host_dir.join("index.html")).unwrap();
+    let link = "articles/a.html__querystring__format=print";
+    assert!(index.contains(&format!("href=\"{}\"", link)));
+    assert_eq!(
+        read_to_string(host_dir.join(link)).unwrap(),
+        "<html><head></head><body>Printable article</body></html>"
+    );
+    assert!(!host_dir.join("articles/a.html").exists());
+}
+
+#[test]
+fn original_paths() {
+    let tempdir = scrape(&["--rewrite-keep-paths"]);
+    let host_dir = Path::new(tempdir.to_str().unwrap()).join("0.0.0.0");
+
+    let index = read_to_string(host_dir.join("index.html")).unwrap();
+    assert!(index.contains("href=\"articles/a.html\""));
+    assert!(read_to_string(host_dir.join("articles/a.html"))
+        .unwrap()
+        .contains("Printable article"));
+}
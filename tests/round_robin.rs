@@ -0,0 +1,41 @@
+//! Tests for the scheduling of the downloads across hosts
+
+mod fixtures;
+
+use std::fs::read_dir;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+#[test]
+fn hosts_take_turns() {
+    // The home page links to many files of a CDN before its own pages
+    let ip = fixtures::spawn_linking_http_server(|port, path| match path {
+        "/" => {
+            let mut links: String = (1..=10)
+                .map(|file| format!("<a href=\"http://cdn.test:{}/{}.txt\">F</a>", port, file))
+                .collect();
+            links.push_str("<a href=\"/a.html\">A</a><a href=\"/b.html\">B</a>");
+            links
+        }
+        _ => "Leaf".to_string(),
+    });
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "--ext-depth", "1"])
+        .args(["-j", "1", "--max-pages", "5"])
+        .args(["--resolve", "cdn.test:*:127.0.0.1"])
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let output_dir = Path::new(output_dir);
+    assert!(output_dir.join("0.0.0.0/a.html").is_file());
+    assert!(output_dir.join("0.0.0.0/b.html").is_file());
+    assert_eq!(read_dir(output_dir.join("cdn.test")).unwrap().count(), 2);
+}
@@ -0,0 +1,86 @@
+//! Tests for the --scope flag
+
+mod fixtures;
+
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+const WWW_HOST: &str = "www.suckit.test";
+const CDN_HOST: &str = "cdn.suckit.test";
+const OTHER_HOST: &str = "other.test";
+
+// The start page links to a file on its host, a file on a sibling subdomain and a file
+// on another domain, all served by the same local server
+fn scrape_with_scope(scope: &str) -> mktemp::Temp {
+    let ip = fixtures::spawn_linking_http_server(|port, _| {
+        [
+            format!("http://{}:{}/local.txt", WWW_HOST, port),
+            format!("http://{}:{}/sibling.txt", CDN_HOST, port),
+            format!("http://{}:{}/external.txt", OTHER_HOST, port),
+        ]
+        .iter()
+        .map(|link| format!("<a href=\"{}\">link</a>", link))
+        .collect()
+    });
+    let port = ip.rsplit(':').next().unwrap();
+    let url = format!("http://{}:{}/", WWW_HOST, port);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let mut args = vec![
+        url,
+        "-o".to_string(),
+        output_dir.to_string(),
+        "--scope".to_string(),
+        scope.to_string(),
+    ];
+    for host in [WWW_HOST, CDN_HOST, OTHER_HOST] {
+        args.push("--resolve".to_string());
+        args.push(format!("{}:*:127.0.0.1", host));
+    }
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args(&args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    tempdir
+}
+
+fn is_downloaded(output_dir: &mktemp::Temp, host: &str, file: &str) -> bool {
+    Path::new(output_dir.as_path())
+        .join(host)
+        .join(file)
+        .is_file()
+}
+
+#[test]
+fn host_scope() {
+    let output_dir = scrape_with_scope("host");
+
+    assert!(is_downloaded(&output_dir, WWW_HOST, "local.txt"));
+    assert!(!is_downloaded(&output_dir, CDN_HOST, "sibling.txt"));
+    assert!(!is_downloaded(&output_dir, OTHER_HOST, "external.txt"));
+}
+
+#[test]
+fn domain_scope() {
+    let output_dir = scrape_with_scope("domain");
+
+    assert!(is_downloaded(&output_dir, WWW_HOST, "local.txt"));
+    assert!(is_downloaded(&output_dir, CDN_HOST, "sibling.txt"));
+    assert!(!is_downloaded(&output_dir, OTHER_HOST, "external.txt"));
+}
+
+#[test]
+fn list_scope() {
+    let output_dir = scrape_with_scope(&format!("list:{},{}", WWW_HOST, OTHER_HOST));
+
+    assert!(is_downloaded(&output_dir, WWW_HOST, "local.txt"));
+    assert!(!is_downloaded(&output_dir, CDN_HOST, "sibling.txt"));
+    assert!(is_downloaded(&output_dir, OTHER_HOST, "external.txt"));
+}
@@ -0,0 +1,71 @@
+//! Tests for the --detect-soft-404s flag
+
+mod fixtures;
+
+use std::path::Path;
+use std::process::Command;
+
+const NOT_FOUND: &str = "<h1>Oops</h1><p>The page {} does not exist, it may have been moved \
+    or deleted. Try the search box, or go back to the home page of the site.</p>";
+
+// The home page links to an article, to a page which doesn't exist but is served with a
+// 200 status and to another one served with a 404 status
+fn scrape(extra_args: &[&str]) -> (mktemp::Temp, String) {
+    let ip = fixtures::spawn_html_http_server(|path| match path {
+        "/" => (
+            200,
+            concat!(
+                "<a href=\"article.html\">Article</a>",
+                "<a href=\"removed.html\">Removed</a>",
+                "<a href=\"gone.html\">Gone</a>",
+            )
+            .to_string(),
+        ),
+        "/article.html" => (200, "<p>A real article, with real content</p>".to_string()),
+        "/gone.html" => (404, NOT_FOUND.replace("{}", path)),
+        _ => (
+            200,
+            NOT_FOUND.replace("{}", path) + "<a href=\"linked-from-404.html\">Link</a>",
+        ),
+    });
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir])
+        .args(extra_args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    (
+        tempdir,
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    )
+}
+
+#[test]
+fn saved_without_detection() {
+    let (output_dir, _) = scrape(&[]);
+    let host_dir = Path::new(output_dir.as_path()).join("0.0.0.0");
+
+    assert!(host_dir.join("removed.html").is_file());
+    assert!(host_dir.join("linked-from-404.html").is_file());
+}
+
+#[test]
+fn soft_404_skipped() {
+    let (output_dir, stdout) = scrape(&["--detect-soft-404s"]);
+    let host_dir = Path::new(output_dir.as_path()).join("0.0.0.0");
+
+    assert!(host_dir.join("index.html").is_file());
+    assert!(host_dir.join("article.html").is_file());
+    assert!(!host_dir.join("removed.html").exists());
+    assert!(!host_dir.join("linked-from-404.html").exists());
+
+    assert!(stdout.contains("Found 2 broken links:"));
+    assert!(stdout.contains("/removed.html: soft 404"));
+    assert!(stdout.contains("/gone.html: HTTP status 404"));
+}
@@ -0,0 +1,83 @@
+//! Tests for the --state-dir and --max-urls-in-memory options
+
+mod fixtures;
+
+use std::fs::read_dir;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// Return the paths of the files in a directory and its subdirectories
+fn list_files(dir: &Path) -> Vec<String> {
+    let mut files = Vec::new();
+    for entry in read_dir(dir).unwrap() {
+        let path = entry.unwrap().path();
+        if path.is_dir() {
+            files.extend(list_files(&path));
+        } else {
+            files.push(path.to_str().unwrap().to_string());
+        }
+    }
+
+    files.sort();
+    files
+}
+
+fn scrape(url: &str, extra_args: &[&str]) -> Vec<String> {
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let status = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([url, "-o", output_dir, "-j", "4"])
+        .args(extra_args)
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    list_files(Path::new(output_dir))
+        .iter()
+        .map(|file| file.trim_start_matches(output_dir).to_string())
+        .collect()
+}
+
+#[test]
+fn same_files_as_in_memory() {
+    // Every page links to its two children, and back to the home page
+    let ip = fixtures::spawn_linking_http_server(|_, path| {
+        let page: u32 = path
+            .trim_start_matches('/')
+            .trim_end_matches(".html")
+            .parse()
+            .unwrap_or(1);
+        let mut links = String::from("<a href=\"/\">Home</a>");
+        if page < 32 {
+            for child in [page * 2, page * 2 + 1] {
+                links.push_str(&format!("<a href=\"/{}.html\">{}</a>", child, child));
+            }
+        }
+        links
+    });
+    let url = format!("http://{}/", ip);
+
+    let state_dir = mktemp::Temp::new_dir().unwrap();
+    let in_memory = scrape(&url, &[]);
+    let in_database = scrape(
+        &url,
+        &[
+            "--state-dir",
+            state_dir.to_str().unwrap(),
+            "--bloom-filter",
+            "1000",
+        ],
+    );
+
+    // The URLs move to a temporary database while the crawl goes on
+    let spilled = scrape(&url, &["--max-urls-in-memory", "8"]);
+
+    assert_eq!(in_memory.len(), 63);
+    assert_eq!(in_database, in_memory);
+    assert_eq!(spilled, in_memory);
+    // The database is deleted once the crawl is over
+    assert_eq!(read_dir(&state_dir).unwrap().count(), 0);
+}
@@ -0,0 +1,98 @@
+//! Tests for the timeout flags
+
+mod fixtures;
+
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const PAGE: &str = "tests/fixtures/";
+const IP: &str = "0.0.0.0";
+
+// A server which never answers must not block the scraping
+#[test]
+fn read_timeout() {
+    let ip = fixtures::spawn_unresponsive_http_server();
+    let url = format!("http://{}", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let start = Instant::now();
+    let cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([
+            &url,
+            "-o",
+            output_dir,
+            "-c",
+            "-t",
+            "2",
+            "--read-timeout",
+            "1",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .unwrap();
+
+    let result = cmd.wait_with_output().unwrap();
+    let stdout_str = String::from_utf8_lossy(&result.stdout);
+    assert!(result.status.success());
+    assert!(stdout_str.contains("Timed out while downloading"));
+    assert!(start.elapsed() < Duration::from_secs(30));
+}
+
+// The request timeout includes the retries
+#[test]
+fn request_timeout() {
+    let ip = fixtures::spawn_unresponsive_http_server();
+    let url = format!("http://{}", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let start = Instant::now();
+    let cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([
+            &url,
+            "-o",
+            output_dir,
+            "-c",
+            "--read-timeout",
+            "1",
+            "--request-timeout",
+            "2",
+        ])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .unwrap();
+
+    let result = cmd.wait_with_output().unwrap();
+    let stdout_str = String::from_utf8_lossy(&result.stdout);
+    assert!(result.status.success());
+    assert!(stdout_str.contains("request took longer than 2s"));
+    // 20 tries of 1 second would take much longer
+    assert!(start.elapsed() < Duration::from_secs(15));
+}
+
+#[test]
+fn max_duration() {
+    let ip = fixtures::spawn_local_http_server(PAGE, false, None);
+    let url = format!("http://{}", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let cmd = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([&url, "-o", output_dir, "--max-duration", "0"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .unwrap();
+
+    let result = cmd.wait_with_output().unwrap();
+    let stdout_str = String::from_utf8_lossy(&result.stdout);
+    assert!(result.status.success());
+    assert!(stdout_str.contains("Maximum duration reached"));
+    assert!(std::fs::read_dir(format!("{}/{}", output_dir, IP)).is_err());
+}
@@ -0,0 +1,62 @@
+//! Tests for the crawler trap detection
+
+mod fixtures;
+
+use std::path::Path;
+use std::process::Command;
+
+fn run_suckit(url: &str, output_dir: &str, extra_args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_suckit"))
+        .args([url, "-o", output_dir])
+        .args(extra_args)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn relative_link_loop() {
+    // Every directory links to a subdirectory of itself, which never ends without the
+    // repeated segment check
+    let ip = fixtures::spawn_linking_http_server(|_, _| "<a href=\"a/\">deeper</a>".to_string());
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    let stdout = run_suckit(&url, output_dir, &["--max-segment-repeats", "2"]);
+    assert!(stdout.contains("it looks like a crawler trap"));
+    assert!(stdout.contains("Skipped 1 URLs looking like crawler traps"));
+
+    let host_dir = Path::new(output_dir).join("0.0.0.0");
+    assert!(host_dir.join("a/a/index.html").is_file());
+    assert!(!host_dir.join("a/a/a/index.html").exists());
+}
+
+#[test]
+fn links_not_followed() {
+    // Each item links to the next one, beyond the depth from the second one
+    let ip = fixtures::spawn_linking_http_server(|_, path| match path {
+        "/" => "<a href=\"/item/1.html\">first</a>".to_string(),
+        "/item/1.html" => "<a href=\"/item/2.html\">next</a>".to_string(),
+        _ => String::new(),
+    });
+    let url = format!("http://{}/", ip);
+
+    let tempdir = mktemp::Temp::new_dir().unwrap();
+    let output_dir = tempdir.to_str().unwrap();
+
+    // The links beyond the depth are not counted in their pattern
+    let stdout = run_suckit(
+        &url,
+        output_dir,
+        &["-d", "1", "--max-urls-per-pattern", "1"],
+    );
+    assert!(!stdout.contains("crawler trap"));
+
+    let host_dir = Path::new(output_dir).join("0.0.0.0");
+    assert!(host_dir.join("item/1.html").is_file());
+    assert!(!host_dir.join("item/2.html").exists());
+}